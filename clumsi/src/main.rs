@@ -6,21 +6,39 @@ extern crate ansi_term;
 extern crate clumsy;
 extern crate rustyline;
 
-use ansi_term::Color;
-use clumsy::ast::Expression;
+use ansi_term::{Color, Style};
+use clumsy::ast;
+use clumsy::ast::{EvalStats, Expression, Limits, Outcome};
+use clumsy::decode;
+use clumsy::decode::Type;
+use clumsy::diagnostic::Diagnostic;
+use clumsy::formatter;
 use clumsy::lexer::Lexer;
 use clumsy::parser;
+use clumsy::primitive;
+use clumsy::printer::Notation;
+use clumsy::scope;
+use clumsy::strategy::Strategy;
+use clumsy::token::Span;
 use rustyline::error::ReadlineError;
+use std::fmt::Display;
 use std::fs;
+use std::io;
+use std::io::Read;
 use std::path::PathBuf;
+use std::process;
 use structopt::StructOpt;
 
 static PROMPT: &str = ">>> ";
 static RED: Color = Color::Fixed(9);
+static HIGHLIGHT: Color = Color::Fixed(11);
 
 #[derive(StructOpt)]
 #[structopt(name = "Clumsy")]
 struct Options {
+    #[structopt(subcommand)]
+    command: Option<Command>,
+
     #[structopt(
         value_name = "file",
         parse(from_os_str),
@@ -42,28 +60,125 @@ struct Options {
         parse(from_os_str)
     )]
     history: Option<PathBuf>,
+
+    /// Keeps unbound variables as named constants instead of reporting them
+    #[structopt(long = "open")]
+    open: bool,
+
+    /// Keeps numbers as integers, with the built-ins add, sub, mul, div, eq, lt, to_church and
+    /// from_church
+    #[structopt(long = "primitives")]
+    primitives: bool,
+
+    /// Reduction strategy: "normal", "applicative", "name" (the default), "need" or "head"
+    #[structopt(long = "strategy", value_name = "strategy")]
+    strategy: Option<Strategy>,
+
+    /// Reduces results to normal form, same as --strategy=normal
+    #[structopt(long = "normalize", conflicts_with = "strategy")]
+    normalize: bool,
+
+    /// Stops after this many β-reductions
+    #[structopt(long = "max-steps", value_name = "steps")]
+    max_steps: Option<usize>,
+
+    /// Stops once the term grows beyond this many nodes
+    #[structopt(long = "max-size", value_name = "nodes")]
+    max_size: Option<usize>,
+
+    /// Prints every reduction step with the redex highlighted
+    #[structopt(long = "trace")]
+    trace: bool,
+
+    /// Prints how much work the reduction took, and how many β-reductions each definition did
+    #[structopt(long = "stats", conflicts_with = "trace")]
+    stats: bool,
+
+    /// Prints bound variables as de Bruijn indices
+    #[structopt(long = "indices")]
+    indices: bool,
+
+    /// Prints results as values of the given type when they encode one, e.g. "list(number)"
+    #[structopt(long = "decode", value_name = "type")]
+    decode: Option<Type>,
+}
+
+#[derive(StructOpt)]
+enum Command {
+    /// Rewrites files in the canonical layout, or prints standard input in it
+    #[structopt(name = "fmt")]
+    Fmt {
+        /// Lists the files that are not formatted instead, failing if there are any
+        #[structopt(long = "check")]
+        check: bool,
+
+        #[structopt(value_name = "file", parse(from_os_str))]
+        files: Vec<PathBuf>,
+    },
+}
+
+struct Settings {
+    translation: ast::Options,
+    strategy: Strategy,
+    limits: Limits<'static>,
+    trace: bool,
+    stats: bool,
+    notation: Notation,
+    decode: Option<Type>,
 }
 
 fn main() {
-    match Options::from_args() {
+    let options = Options::from_args();
+    let settings = Settings {
+        translation: ast::Options {
+            open_terms: options.open,
+            primitives: options.primitives,
+            ..ast::Options::default()
+        },
+        strategy: if options.normalize {
+            Strategy::NormalOrder
+        } else {
+            options.strategy.unwrap_or_default()
+        },
+        limits: Limits {
+            steps: options.max_steps,
+            size: options.max_size,
+            cancelled: None,
+        },
+        trace: options.trace,
+        stats: options.stats,
+        notation: if options.indices {
+            Notation::Indices
+        } else {
+            Notation::Named
+        },
+        decode: options.decode.clone(),
+    };
+
+    match options {
+        Options {
+            command: Some(Command::Fmt { check, ref files }),
+            ..
+        } => fmt(files, check),
+
         Options {
             expression: Some(ref expression),
             ..
-        } => eval(expression),
+        } => eval(expression, &settings),
 
         Options {
             program: Some(path),
             ..
         } => match fs::read_to_string(path) {
-            Ok(ref source) => eval(source),
+            Ok(ref source) => eval(source, &settings),
             Err(error) => println!("{}", RED.paint(error.to_string())),
         },
 
-        Options { ref history, .. } => repl(history),
+        Options { ref history, .. } => repl(history, &settings),
     }
 }
 
-fn repl(history: &Option<PathBuf>) {
+fn repl(history: &Option<PathBuf>, settings: &Settings) {
     let mut rl = rustyline::Editor::<()>::new().history_ignore_dups(true);
     if let Some(history) = history {
         let _ = rl.load_history(history);
@@ -73,7 +188,7 @@ fn repl(history: &Option<PathBuf>) {
         match rl.readline(PROMPT) {
             Ok(source) => {
                 rl.add_history_entry(&source);
-                eval(&source);
+                eval(&source, settings);
             }
             Err(ReadlineError::Eof) => break,
             Err(error) => println!("{}", RED.paint(error.to_string())),
@@ -85,13 +200,190 @@ fn repl(history: &Option<PathBuf>) {
     }
 }
 
-fn eval(source: &str) {
-    let tokens = &mut Lexer::new(source);
-    match parser::parse(tokens).map(|cst| {
-        let expression = Expression::from_cst_program(&cst);
-        expression.evaluate()
-    }) {
-        Ok(result) => println!("{}", result),
-        Err(error) => println!("{}", RED.paint(error)),
+fn eval(source: &str, settings: &Settings) {
+    let (cst, errors) = parser::parse_with_recovery(&mut Lexer::new(source));
+    if !errors.is_empty() {
+        for error in errors {
+            print_error(source, error.span(), error);
+        }
+        return;
+    }
+
+    let errors = ast::check_numbers(&cst, &settings.translation);
+    if !errors.is_empty() {
+        for error in errors {
+            print_error(source, error.span, error);
+        }
+        return;
+    }
+
+    if !settings.translation.open_terms {
+        let errors = if settings.translation.primitives {
+            scope::check_with(&cst, primitive::is_builtin)
+        } else {
+            scope::check(&cst)
+        };
+        if !errors.is_empty() {
+            for error in errors {
+                print_error(source, error.span, error);
+            }
+            return;
+        }
+    }
+
+    let expression = match Expression::from_cst_program_with(&cst, &settings.translation) {
+        Ok(expression) => expression,
+        Err(error) => return print_error(source, error.span(), error),
     };
+    if settings.trace {
+        trace(expression, settings);
+        return;
+    }
+
+    let (outcome, stats) = expression.reduce_with_stats(settings.strategy, &settings.limits);
+    match outcome {
+        Outcome::Normal(..) => {}
+        Outcome::StepLimit(..) => println!("{}", RED.paint("Step limit reached, stopped at:")),
+        Outcome::SizeLimit(..) => println!("{}", RED.paint("Size limit reached, stopped at:")),
+        Outcome::Cancelled(..) => println!("{}", RED.paint("Cancelled, stopped at:")),
+    }
+
+    print_result(&outcome.into_expression(), settings);
+    if settings.stats {
+        print_stats(&stats);
+    }
+}
+
+fn fmt(files: &[PathBuf], check: bool) {
+    let mut failed = false;
+
+    if files.is_empty() {
+        let mut source = String::new();
+        if let Err(error) = io::stdin().read_to_string(&mut source) {
+            println!("{}", RED.paint(error.to_string()));
+            process::exit(1);
+        }
+        match format(&source) {
+            Some(ref formatted) if check => failed = *formatted != source,
+            Some(formatted) => print!("{}", formatted),
+            None => failed = true,
+        }
+    }
+
+    for path in files {
+        let source = match fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(error) => {
+                println!("{}: {}", path.display(), RED.paint(error.to_string()));
+                failed = true;
+                continue;
+            }
+        };
+        match format(&source) {
+            Some(ref formatted) if *formatted == source => (),
+            Some(_) if check => {
+                println!("{}", path.display());
+                failed = true;
+            }
+            Some(formatted) => {
+                if let Err(error) = fs::write(path, formatted) {
+                    println!("{}: {}", path.display(), RED.paint(error.to_string()));
+                    failed = true;
+                }
+            }
+            None => failed = true,
+        }
+    }
+
+    if failed {
+        process::exit(1);
+    }
+}
+
+/// Prints the syntax errors instead if there are any.
+fn format(source: &str) -> Option<String> {
+    let (cst, errors) = parser::parse_with_recovery(&mut Lexer::lossless(source));
+    if !errors.is_empty() {
+        for error in errors {
+            print_error(source, error.span(), error);
+        }
+        return None;
+    }
+    Some(formatter::format(&cst))
+}
+
+fn trace(expression: Expression, settings: &Settings) {
+    let style = Style::from(HIGHLIGHT).bold();
+    let (before, after) = (style.prefix().to_string(), style.suffix().to_string());
+
+    // Stops where `Expression::reduce_with_limits` does: step `n` comes after `n` contractions,
+    // and the size is only checked after a contraction.
+    let limits = &settings.limits;
+    for (n, step) in expression.steps(settings.strategy).enumerate() {
+        if n > 0 && limits.size.map_or(false, |max| step.expression.size() > max) {
+            return stop("Size limit reached", &step.expression, settings);
+        }
+
+        let redex = match step.redex {
+            Some(ref redex) => redex,
+            None => return print_result(&step.expression, settings),
+        };
+
+        if limits.steps.map_or(false, |max| n >= max) {
+            return stop("Step limit reached", &step.expression, settings);
+        }
+        if limits.is_cancelled() {
+            return stop("Cancelled", &step.expression, settings);
+        }
+
+        println!(
+            "{:>4}: {}",
+            n,
+            step.expression
+                .display(settings.notation)
+                .highlight(redex, &before, &after)
+        );
+    }
+}
+
+fn stop(message: &str, expression: &Expression, settings: &Settings) {
+    println!("{}", RED.paint(format!("{}, stopped at:", message)));
+    print_result(expression, settings);
+}
+
+fn print_result(expression: &Expression, settings: &Settings) {
+    match settings
+        .decode
+        .as_ref()
+        .and_then(|ty| decode::decode(expression, ty, &settings.limits))
+    {
+        Some(value) => println!("{}", value),
+        None => println!("{}", expression.display(settings.notation)),
+    }
+}
+
+fn print_stats(stats: &EvalStats) {
+    println!("β-reductions:  {}", stats.beta_reductions);
+    println!("δ-reductions:  {}", stats.delta_reductions);
+    println!("substitutions: {}", stats.substitutions);
+    println!("shifts:        {}", stats.shifts);
+    println!("peak size:     {}", stats.peak_size);
+    println!("time:          {:?}", stats.time);
+
+    let mut definitions = stats.definitions.iter().collect::<Vec<_>>();
+    definitions.sort_by(|(a, m), (b, n)| n.cmp(m).then(a.cmp(b)));
+    let width = definitions.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    for (name, count) in definitions {
+        println!("  {:<width$} {}", name, count, width = width);
+    }
+}
+
+fn print_error<T>(source: &str, span: Span, message: T)
+where
+    T: Display,
+{
+    println!(
+        "{}",
+        RED.paint(Diagnostic::new(source, span, message).to_string())
+    );
 }
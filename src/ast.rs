@@ -1,13 +1,38 @@
 use cst::{Expression as CSTExpression, *};
+use lazy;
+use lexer::Lexer;
+use natural::Natural;
+use parser;
+use primitive;
+use primitive::{Primitive, CONVERSIONS};
+use printer::{Notation, Printer};
+use std::cmp;
+use std::collections::HashMap;
 use std::fmt;
 use std::fmt::{Display, Formatter};
+use std::mem;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+use strategy::Strategy;
+use token::Span;
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug)]
 pub enum Expression {
     Variable {
         index: Option<usize>,
     },
+    Constant {
+        name: String,
+    },
+    /// Only built with `Options::primitives`.
+    Integer {
+        value: Natural,
+    },
+    Primitive {
+        primitive: Primitive,
+    },
     Abstraction {
+        name: Name,
         expression: Box<Expression>,
     },
     Application {
@@ -16,76 +41,392 @@ pub enum Expression {
     },
 }
 
+/// The source name of a bound variable, and where its abstraction was written.
+///
+/// It only serves as a hint for printing and profiling, so any two names compare equal.
+#[derive(Debug, Clone, Default)]
+pub struct Name {
+    pub hint: Option<Rc<str>>,
+    /// The top-level `let` the abstraction is written in, if any.
+    pub definition: Option<Rc<str>>,
+}
+
+impl PartialEq for Name {
+    fn eq(&self, _: &Name) -> bool {
+        true
+    }
+}
+
+impl<'a> From<&'a str> for Name {
+    fn from(name: &'a str) -> Name {
+        Name {
+            hint: Some(name.into()),
+            definition: None,
+        }
+    }
+}
+
+/// Options for translating a CST into an `Expression`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Options {
+    /// Keeps unbound variables as named `Expression::Constant`s instead of unknown indices.
+    pub open_terms: bool,
+    /// Keeps number literals as `Expression::Integer`s and makes the names built into
+    /// `primitive` available.
+    pub primitives: bool,
+    /// How string literals are desugared into lists of characters.
+    pub lists: ListEncoding,
+}
+
+/// The list encodings string literals can desugar into, both understood by `decode::string`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ListEncoding {
+    /// Right folds: `\c n. c 'a' (c 'b' n)`
+    Church,
+    /// `\n c. c 'a' (\n c. c 'b' (\n c. n))`
+    Scott,
+}
+
+impl Default for ListEncoding {
+    fn default() -> ListEncoding {
+        ListEncoding::Church
+    }
+}
+
+/// The largest number literal unfolded into a Church numeral, which takes a node per unit.
+pub const MAX_CHURCH_NUMERAL: u64 = 1_000_000;
+
+/// A number literal too large to unfold into a Church numeral.
+#[derive(Debug, PartialEq, Clone)]
+pub struct NumberTooLarge {
+    pub value: String,
+    pub span: Span,
+}
+
+impl Display for NumberTooLarge {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Number {} is too large for a Church numeral, the largest is {}",
+            self.value, MAX_CHURCH_NUMERAL
+        )
+    }
+}
+
+/// Why a program cannot be translated into an `Expression`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum TranslationError {
+    NumberTooLarge(NumberTooLarge),
+    /// The last statement is not an expression, which `parser::parse` reports but
+    /// `parser::parse_with_recovery` keeps as an error statement.
+    MissingExpression { span: Span },
+}
+
+impl TranslationError {
+    pub fn span(&self) -> Span {
+        match self {
+            TranslationError::NumberTooLarge(NumberTooLarge { span, .. })
+            | TranslationError::MissingExpression { span } => *span,
+        }
+    }
+}
+
+impl From<NumberTooLarge> for TranslationError {
+    fn from(error: NumberTooLarge) -> TranslationError {
+        TranslationError::NumberTooLarge(error)
+    }
+}
+
+impl Display for TranslationError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            TranslationError::NumberTooLarge(error) => error.fmt(f),
+            TranslationError::MissingExpression { .. } => {
+                write!(f, "The last statement of a program must be an expression")
+            }
+        }
+    }
+}
+
+/// Finds the number literals that cannot be translated with `options`. Only Church numerals
+/// are limited in size.
+pub fn check_numbers(program: &Program, options: &Options) -> Vec<NumberTooLarge> {
+    fn check_expression(expression: &CSTExpression, errors: &mut Vec<NumberTooLarge>) {
+        match expression {
+            CSTExpression::Number(Number { value, span, .. }) => {
+                if value.parse::<u64>().map_or(true, |n| n > MAX_CHURCH_NUMERAL) {
+                    errors.push(NumberTooLarge {
+                        value: value.clone(),
+                        span: *span,
+                    });
+                }
+            }
+            CSTExpression::Abstraction(abstraction) => {
+                check_expression(&abstraction.expression, errors)
+            }
+            CSTExpression::Application(application) => {
+                for expression in &application.expressions {
+                    check_expression(expression, errors);
+                }
+            }
+            CSTExpression::Let(let_expression) => {
+                for binding in &let_expression.bindings {
+                    check_expression(&binding.expression, errors);
+                }
+                check_expression(&let_expression.expression, errors);
+            }
+            CSTExpression::Variable(..)
+            | CSTExpression::Character(..)
+            | CSTExpression::String(..) => {}
+        }
+    }
+
+    let Program(statements) = program;
+    let mut errors = Vec::new();
+    if !options.primitives {
+        for statement in statements {
+            match statement {
+                Statement::Expression(ExpressionStatement { expression, .. })
+                | Statement::Let(LetStatement { expression, .. }) => {
+                    check_expression(expression, &mut errors)
+                }
+                Statement::LetRec(LetRecStatement { bindings, .. }) => {
+                    for binding in bindings {
+                        check_expression(&binding.expression, &mut errors);
+                    }
+                }
+                Statement::Error(..) => {}
+            }
+        }
+    }
+    errors
+}
+
+/// Bounds on how much work a reduction may do.
+#[derive(Clone, Copy, Default)]
+pub struct Limits<'a> {
+    /// The maximum number of β- and δ-reductions. Call-by-need only counts β-reductions.
+    pub steps: Option<usize>,
+    /// The maximum number of nodes in the term. Call-by-need never builds the term, so it
+    /// limits the number of thunks and stack frames the machine holds instead.
+    pub size: Option<usize>,
+    /// Called before every β-reduction; reduction stops once it returns `true`.
+    pub cancelled: Option<&'a dyn Fn() -> bool>,
+}
+
+/// What a reduction did, for comparing the cost of terms.
+#[derive(Debug, Clone, Default)]
+pub struct EvalStats {
+    pub beta_reductions: usize,
+    /// Applications of primitives.
+    pub delta_reductions: usize,
+    /// Variables replaced by an argument. Call-by-need counts thunk lookups instead.
+    pub substitutions: usize,
+    /// Variable indices adjusted when moving terms under or out of binders. Call-by-need never
+    /// shifts.
+    pub shifts: usize,
+    /// The largest number of nodes the term had. Call-by-need counts the most thunks and stack
+    /// frames instead, like `Limits::size`.
+    pub peak_size: usize,
+    /// Only measured by `Expression::reduce_with_stats`.
+    pub time: Duration,
+    /// β-reductions of abstractions written in each top-level `let`, by its variable.
+    pub definitions: HashMap<Rc<str>, usize>,
+}
+
+/// How a limited reduction ended, along with the term it ended at.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Outcome {
+    Normal(Expression),
+    StepLimit(Expression),
+    SizeLimit(Expression),
+    Cancelled(Expression),
+}
+
+impl<'a> Limits<'a> {
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.map_or(false, |cancelled| cancelled())
+    }
+}
+
+impl EvalStats {
+    /// Counts contracting a redex whose abstraction has `name`.
+    pub(crate) fn reduction(&mut self, name: &Name) {
+        self.beta_reductions += 1;
+        if let Some(ref definition) = name.definition {
+            *self.definitions.entry(definition.clone()).or_insert(0) += 1;
+        }
+    }
+}
+
+impl Outcome {
+    pub fn expression(&self) -> &Expression {
+        match self {
+            Outcome::Normal(expression)
+            | Outcome::StepLimit(expression)
+            | Outcome::SizeLimit(expression)
+            | Outcome::Cancelled(expression) => expression,
+        }
+    }
+
+    pub fn into_expression(self) -> Expression {
+        match self {
+            Outcome::Normal(expression)
+            | Outcome::StepLimit(expression)
+            | Outcome::SizeLimit(expression)
+            | Outcome::Cancelled(expression) => expression,
+        }
+    }
+}
+
 impl Expression {
-    pub fn from_cst_program(value: &Program) -> Expression {
+    pub fn from_cst_program(value: &Program) -> Result<Expression, TranslationError> {
+        Expression::from_cst_program_with(value, &Options::default())
+    }
+
+    /// Translates a program, failing on the first number literal `check_numbers` finds or if
+    /// the last statement is not an expression.
+    pub fn from_cst_program_with(
+        value: &Program,
+        options: &Options,
+    ) -> Result<Expression, TranslationError> {
+        if let Some(error) = check_numbers(value, options).into_iter().next() {
+            return Err(error.into());
+        }
+
         let Program(statements) = value;
 
         let mut iter = statements.iter();
 
-        let innermost = if let Some(Statement::Expression(ExpressionStatement { expression })) =
-            iter.next_back()
-        {
-            expression
-        } else {
-            unimplemented!()
+        let innermost = match iter.next_back() {
+            Some(Statement::Expression(ExpressionStatement { expression, .. })) => expression,
+            last => {
+                return Err(TranslationError::MissingExpression {
+                    span: last.map_or_else(Span::default, Statement::span),
+                })
+            }
         };
 
         let mut scopes = iter
             .clone()
-            .filter_map(|statement| match statement {
+            .flat_map(|statement| match statement {
                 Statement::Let(LetStatement {
-                    variable: Identifier(variable),
+                    variable: Identifier { name, .. },
                     ..
-                }) => Some(variable.as_str()),
-                _ => None,
+                }) => vec![name.as_str()],
+                Statement::LetRec(LetRecStatement { bindings, .. }) => bindings
+                    .iter()
+                    .map(|binding| binding.variable.name.as_str())
+                    .collect(),
+                _ => Vec::new(),
             })
             .collect();
 
         let mut outer_scope = Vec::new();
         let result = iter.rfold(
-            Expression::from_cst_expression(innermost, &mut scopes),
+            Expression::from_cst_expression(innermost, &mut scopes, options),
             |inner, statement| match statement {
-                Statement::Expression(..) => unimplemented!(),
+                // Only the last expression statement is evaluated.
+                Statement::Expression(..) | Statement::Error(..) => inner,
                 Statement::Let(LetStatement { expression, .. }) => {
-                    outer_scope.push(scopes.pop().unwrap());
+                    let variable = scopes.pop().unwrap();
+                    outer_scope.push(variable);
                     Expression::Application {
                         applicand: box Expression::Abstraction {
+                            name: Name::from(variable),
                             expression: box inner,
                         },
-                        argument: box Expression::from_cst_expression(expression, &mut scopes),
+                        argument: box Expression::from_cst_expression(
+                            expression,
+                            &mut scopes,
+                            options,
+                        ).defined_in(variable),
                     }
                 }
+                Statement::LetRec(LetRecStatement { bindings, .. }) => {
+                    for _ in bindings {
+                        outer_scope.push(scopes.pop().unwrap());
+                    }
+                    let group =
+                        Expression::recursive_from_cst(bindings, &mut scopes, options, true);
+                    Expression::bind(bindings, inner, group)
+                }
             },
         );
         outer_scope
             .into_iter()
             .rfold((), |_, variable| scopes.push(variable));
-        result
+        Ok(result)
     }
 
-    fn from_cst_expression<'a>(value: &'a CSTExpression, scopes: &mut Vec<&'a str>) -> Expression {
+    /// Marks every abstraction in `self` as written in the definition of `variable`.
+    fn defined_in(mut self, variable: &str) -> Expression {
+        let definition: Rc<str> = variable.into();
+        let mut stack = vec![&mut self];
+        while let Some(expression) = stack.pop() {
+            match expression {
+                Expression::Variable { .. }
+                | Expression::Constant { .. }
+                | Expression::Integer { .. }
+                | Expression::Primitive { .. } => {}
+                Expression::Abstraction { name, expression } => {
+                    name.definition = Some(definition.clone());
+                    stack.push(expression);
+                }
+                Expression::Application {
+                    applicand,
+                    argument,
+                } => {
+                    stack.push(argument);
+                    stack.push(applicand);
+                }
+            }
+        }
+        self
+    }
+
+    fn from_cst_expression<'a>(
+        value: &'a CSTExpression,
+        scopes: &mut Vec<&'a str>,
+        options: &Options,
+    ) -> Expression {
         match value {
-            CSTExpression::Variable(variable) => Expression::variable_from_cst(variable, scopes),
+            CSTExpression::Variable(variable) => {
+                Expression::variable_from_cst(variable, scopes, options)
+            }
 
             CSTExpression::Abstraction(abstraction) => {
-                Expression::abstraction_from_cst(abstraction, scopes)
+                Expression::abstraction_from_cst(abstraction, scopes, options)
             }
 
             CSTExpression::Application(application) => match application.expressions.len() {
                 0 => panic!(),
-                1 => Expression::from_cst_expression(&application.expressions[0], scopes),
-                _ => Expression::application_from_cst(application, scopes),
+                1 => Expression::from_cst_expression(&application.expressions[0], scopes, options),
+                _ => Expression::application_from_cst(application, scopes, options),
             },
 
-            CSTExpression::Number(number) => Expression::from_number(number),
+            CSTExpression::Let(let_expression) => {
+                Expression::let_from_cst(let_expression, scopes, options)
+            }
+
+            CSTExpression::Number(number) => Expression::from_number(number, options),
 
             CSTExpression::Character(character) => Expression::from_character(character),
+
+            CSTExpression::String(string) => Expression::from_string(string, options),
         }
     }
 
-    fn variable_from_cst(value: &VariableExpression, scopes: &mut Vec<&str>) -> Expression {
+    fn variable_from_cst(
+        value: &VariableExpression,
+        scopes: &mut Vec<&str>,
+        options: &Options,
+    ) -> Expression {
         let VariableExpression {
-            identifier: Identifier(identifier),
+            identifier: Identifier {
+                name: identifier, ..
+            },
+            ..
         } = value;
 
         let index = scopes
@@ -93,25 +434,34 @@ impl Expression {
             .rposition(|variable| variable == identifier)
             .map(|index| scopes.len() - index - 1);
 
-        Expression::Variable { index }
+        match index {
+            None if options.primitives && primitive::is_builtin(identifier) => {
+                Expression::builtin(identifier)
+            }
+            None if options.open_terms => Expression::Constant {
+                name: identifier.clone(),
+            },
+            _ => Expression::Variable { index },
+        }
     }
 
     fn abstraction_from_cst<'a>(
         value: &'a AbstractionExpression,
         scopes: &mut Vec<&'a str>,
+        options: &Options,
     ) -> Expression {
-        for Identifier(parameter) in &value.parameters {
-            scopes.push(parameter);
+        for Identifier { name, .. } in &value.parameters {
+            scopes.push(name);
         }
 
-        let result = value.parameters.iter().skip(1).rfold(
-            Expression::Abstraction {
-                expression: box Expression::from_cst_expression(&*value.expression, scopes),
-            },
-            |body, _| Expression::Abstraction {
+        let body = Expression::from_cst_expression(&value.expression, scopes, options);
+        let result = value
+            .parameters
+            .iter()
+            .rfold(body, |body, parameter| Expression::Abstraction {
+                name: Name::from(parameter.name.as_str()),
                 expression: box body,
-            },
-        );
+            });
 
         for _ in &value.parameters {
             scopes.pop();
@@ -123,45 +473,216 @@ impl Expression {
     fn application_from_cst<'a>(
         value: &'a ApplicationExpression,
         scopes: &mut Vec<&'a str>,
+        options: &Options,
     ) -> Expression {
         let mut iter = value.expressions.iter();
         let callee = iter.next().unwrap();
         let argument = iter.next().unwrap();
         iter.fold(
             Expression::Application {
-                applicand: box Expression::from_cst_expression(callee, scopes),
-                argument: box Expression::from_cst_expression(argument, scopes),
+                applicand: box Expression::from_cst_expression(callee, scopes, options),
+                argument: box Expression::from_cst_expression(argument, scopes, options),
             },
             |callee, argument| Expression::Application {
                 applicand: box callee,
-                argument: box Expression::from_cst_expression(argument, scopes),
+                argument: box Expression::from_cst_expression(argument, scopes, options),
             },
         )
     }
 
-    fn from_number(value: &Number) -> Expression {
-        let Number(value) = value;
-        let mut n = value.parse::<usize>().unwrap(); // TODO: handle this
-        let mut result = Expression::Variable { index: Some(0) };
+    /// `let x = a, y = b in c` is `(\x. (\y. c) b) a`.
+    fn let_from_cst<'a>(
+        value: &'a LetExpression,
+        scopes: &mut Vec<&'a str>,
+        options: &Options,
+    ) -> Expression {
+        if value.recursive {
+            let group = Expression::recursive_from_cst(&value.bindings, scopes, options, false);
+            for binding in &value.bindings {
+                scopes.push(&binding.variable.name);
+            }
+            let body = Expression::from_cst_expression(&value.expression, scopes, options);
+            for _ in &value.bindings {
+                scopes.pop();
+            }
+            return Expression::bind(&value.bindings, body, group);
+        }
 
-        while n > 0 {
-            result = Expression::Application {
-                applicand: box Expression::Variable { index: Some(1) },
-                argument: box result,
-            };
-            n -= 1;
+        let arguments: Vec<_> = value
+            .bindings
+            .iter()
+            .map(|binding| {
+                let argument =
+                    Expression::from_cst_expression(&binding.expression, scopes, options);
+                scopes.push(&binding.variable.name);
+                argument
+            })
+            .collect();
+
+        let body = Expression::from_cst_expression(&value.expression, scopes, options);
+        for _ in &value.bindings {
+            scopes.pop();
         }
 
-        Expression::Abstraction {
-            expression: box Expression::Abstraction {
-                expression: box result,
+        value
+            .bindings
+            .iter()
+            .zip(arguments)
+            .rfold(body, |body, (binding, argument)| Expression::Application {
+                applicand: box Expression::Abstraction {
+                    name: Name::from(binding.variable.name.as_str()),
+                    expression: box body,
+                },
+                argument: box argument,
+            })
+    }
+
+    /// The value of a `let rec` group, closed over `scopes`. A single `let rec f = a` is
+    /// `Z (\f. a)`. A group `f1 = a1 and .. fn = an` is the fixed point of the tuple of all
+    /// `\f1 .. fn. ai`, from which `bind` takes each `fj`.
+    ///
+    /// `Z` is the fixed point combinator `Y` with `x x` η-expanded, so that it also terminates
+    /// when arguments are reduced first.
+    fn recursive_from_cst<'a>(
+        bindings: &'a [Binding],
+        scopes: &mut Vec<&'a str>,
+        options: &Options,
+        top_level: bool,
+    ) -> Expression {
+        fn abstraction(name: &str, expression: Expression) -> Expression {
+            Expression::Abstraction {
+                name: Name::from(name),
+                expression: box expression,
+            }
+        }
+
+        fn application(applicand: Expression, argument: Expression) -> Expression {
+            Expression::Application {
+                applicand: box applicand,
+                argument: box argument,
+            }
+        }
+
+        fn variable(index: usize) -> Expression {
+            Expression::Variable { index: Some(index) }
+        }
+
+        for binding in bindings {
+            scopes.push(&binding.variable.name);
+        }
+        let mut functions: Vec<_> = bindings
+            .iter()
+            .map(|binding| {
+                let body = Expression::from_cst_expression(&binding.expression, scopes, options);
+                let function = bindings.iter().rfold(body, |body, binding| {
+                    abstraction(&binding.variable.name, body)
+                });
+                if top_level {
+                    function.defined_in(&binding.variable.name)
+                } else {
+                    function
+                }
+            })
+            .collect();
+        for _ in bindings {
+            scopes.pop();
+        }
+
+        // \f. (\x. f (\v. x x v)) (\x. f (\v. x x v))
+        let half = abstraction(
+            "x",
+            application(
+                variable(1),
+                abstraction(
+                    "v",
+                    application(application(variable(1), variable(1)), variable(0)),
+                ),
+            ),
+        );
+        let fixed_point = abstraction("f", application(half.clone(), half));
+
+        let n = bindings.len();
+        if n == 1 {
+            return application(fixed_point, functions.pop().unwrap());
+        }
+
+        let projection =
+            |j: usize| (0..n).fold(variable(n - 1 - j), |body, _| abstraction("x", body));
+
+        // Z (\t s. s (F1 f1 .. fn) .. (Fn f1 .. fn)) under \F1 .. Fn, with each fj being
+        // \v. t πj v.
+        let tuple = (0..n).fold(variable(0), |tuple, i| {
+            let component = (0..n).fold(variable(n - i + 1), |function, j| {
+                let projected = application(application(variable(2), projection(j)), variable(0));
+                application(function, abstraction("v", projected))
+            });
+            application(tuple, component)
+        });
+        let body = application(fixed_point, abstraction("t", abstraction("s", tuple)));
+        functions.into_iter().fold(
+            bindings.iter().rfold(body, |body, binding| {
+                abstraction(&binding.variable.name, body)
+            }),
+            application,
+        )
+    }
+
+    /// Binds the value of a `let rec` group to its `bindings`: a single one as `(\f. body) a`,
+    /// and a group by passing its components to `\f1 .. fn. body`, so that the tuple is only
+    /// built once.
+    fn bind(bindings: &[Binding], body: Expression, value: Expression) -> Expression {
+        let function = bindings.iter().rfold(body, |body, binding| Expression::Abstraction {
+            name: Name::from(binding.variable.name.as_str()),
+            expression: box body,
+        });
+        let (applicand, argument) = if bindings.len() == 1 {
+            (function, value)
+        } else {
+            (value, function)
+        };
+        Expression::Application {
+            applicand: box applicand,
+            argument: box argument,
+        }
+    }
+
+    /// Translates a name built into `primitive`.
+    fn builtin(name: &str) -> Expression {
+        if let Some(primitive) = Primitive::from_name(name) {
+            return Expression::Primitive { primitive };
+        }
+
+        let &(_, source) = CONVERSIONS.iter().find(|&&(n, _)| n == name).unwrap();
+        let program = parser::parse(&mut Lexer::new(source)).unwrap();
+        Expression::from_cst_program_with(
+            &program,
+            &Options {
+                primitives: true,
+                ..Options::default()
             },
+        ).expect("integers have no limit")
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the number is too large for a Church numeral, which `from_cst_program_with`
+    /// checks first.
+    fn from_number(value: &Number, options: &Options) -> Expression {
+        let Number { value, .. } = value;
+        if options.primitives {
+            return Expression::Integer {
+                value: value.parse().expect("the lexer only reads digits"),
+            };
         }
+
+        Expression::church(
+            value
+                .parse::<u64>()
+                .expect("checked by `check_numbers`"),
+        )
     }
 
-    fn from_character(value: &Character) -> Expression {
-        let Character(value) = value;
-        let mut n = *value as u32;
+    fn church(mut n: u64) -> Expression {
         let mut result = Expression::Variable { index: Some(0) };
 
         while n > 0 {
@@ -173,130 +694,695 @@ impl Expression {
         }
 
         Expression::Abstraction {
+            name: Name::from("f"),
             expression: box Expression::Abstraction {
+                name: Name::from("x"),
                 expression: box result,
             },
         }
     }
 
-    pub fn is_reducible(&self) -> bool {
-        match self {
-            Expression::Variable { .. } => false,
-            Expression::Abstraction { .. } => false,
-            Expression::Application { box applicand, .. } => applicand.is_reducible_inner(),
+    fn from_string(value: &StringLiteral, options: &Options) -> Expression {
+        let StringLiteral { value, span, .. } = value;
+        let characters = value
+            .chars()
+            .rev()
+            .map(|c| Expression::from_character(&Character::new(c, *span)));
+
+        match options.lists {
+            ListEncoding::Church => {
+                let mut result = Expression::Variable { index: Some(0) };
+                for character in characters {
+                    result = Expression::Application {
+                        applicand: box Expression::Application {
+                            applicand: box Expression::Variable { index: Some(1) },
+                            argument: box character,
+                        },
+                        argument: box result,
+                    };
+                }
+
+                Expression::Abstraction {
+                    name: Name::from("c"),
+                    expression: box Expression::Abstraction {
+                        name: Name::from("n"),
+                        expression: box result,
+                    },
+                }
+            }
+
+            ListEncoding::Scott => {
+                let cons = |expression| Expression::Abstraction {
+                    name: Name::from("n"),
+                    expression: box Expression::Abstraction {
+                        name: Name::from("c"),
+                        expression: box expression,
+                    },
+                };
+                let mut result = cons(Expression::Variable { index: Some(1) });
+                for character in characters {
+                    result = cons(Expression::Application {
+                        applicand: box Expression::Application {
+                            applicand: box Expression::Variable { index: Some(0) },
+                            argument: box character,
+                        },
+                        argument: box result,
+                    });
+                }
+                result
+            }
         }
     }
 
-    fn is_reducible_inner(&self) -> bool {
-        match self {
-            Expression::Variable { .. } => false,
-            Expression::Abstraction { .. } => true,
-            Expression::Application { box applicand, .. } => applicand.is_reducible_inner(),
+    /// Code points above 255 are built from their base 256 digits, `\f x. m (256 f) (d f x)`
+    /// for `256 m + d`, instead of as an application chain hundreds of thousands deep. Both
+    /// normalize to the same numeral.
+    fn from_character(value: &Character) -> Expression {
+        let Character { value, .. } = value;
+        let mut digits = Vec::new();
+        let mut n = *value as u64;
+        while n >= 256 {
+            digits.push(n % 256);
+            n /= 256;
         }
+
+        let mut result = Expression::church(n);
+        for &digit in digits.iter().rev() {
+            result = Expression::Abstraction {
+                name: Name::from("f"),
+                expression: box Expression::Abstraction {
+                    name: Name::from("x"),
+                    expression: box Expression::Application {
+                        applicand: box Expression::Application {
+                            applicand: box result,
+                            argument: box Expression::Application {
+                                applicand: box Expression::church(256),
+                                argument: box Expression::Variable { index: Some(1) },
+                            },
+                        },
+                        argument: box Expression::Application {
+                            applicand: box Expression::Application {
+                                applicand: box Expression::church(digit),
+                                argument: box Expression::Variable { index: Some(1) },
+                            },
+                            argument: box Expression::Variable { index: Some(0) },
+                        },
+                    },
+                },
+            };
+        }
+        result
+    }
+
+    pub fn display(&self, notation: Notation) -> Printer<'_> {
+        Printer::new(self, notation)
+    }
+
+    pub fn is_reducible(&self) -> bool {
+        self.redex(Strategy::CallByName).is_some()
     }
 
-    pub fn evaluate(mut self) -> Expression {
-        while self.is_reducible() {
-            self = self.evaluate1();
+    pub fn evaluate(self) -> Expression {
+        self.reduce(Strategy::CallByName)
+    }
+
+    /// Reduces to β-normal form in normal order, going under abstractions and into the
+    /// arguments of stuck applications. Does not return if there is no normal form.
+    pub fn normalize(self) -> Expression {
+        self.reduce(Strategy::NormalOrder)
+    }
+
+    /// Reduces with `strategy` until it finds no more redexes.
+    pub fn reduce(mut self, strategy: Strategy) -> Expression {
+        if strategy == Strategy::CallByNeed {
+            return lazy::evaluate(&self);
+        }
+
+        let mut stats = EvalStats::default();
+        while let Some(path) = self.redex(strategy) {
+            self.subterm_mut(&path).contract(&mut stats);
         }
         self
     }
 
-    fn evaluate1(self) -> Expression {
-        match self {
-            Expression::Application {
-                applicand: box Expression::Abstraction { expression },
-                box argument,
-            } => expression
-                .substituted(0, argument.shifted(1, 0))
-                .shifted(-1, 0),
+    /// Iterates over the terms that reducing with `strategy` passes through, starting with
+    /// `self` and ending with its normal form.
+    pub fn steps(self, strategy: Strategy) -> Steps {
+        Steps {
+            expression: Some(self),
+            strategy,
+        }
+    }
 
-            Expression::Application {
-                applicand,
-                argument,
-            } => Expression::Application {
-                applicand: box applicand.evaluate1(),
-                argument,
-            },
+    /// Like `evaluate`, but gives up once any of `limits` is exceeded.
+    pub fn evaluate_with_limits(self, limits: &Limits) -> Outcome {
+        self.reduce_with_limits(Strategy::CallByName, limits)
+    }
+
+    /// Like `reduce`, but gives up once any of `limits` is exceeded.
+    pub fn reduce_with_limits(self, strategy: Strategy, limits: &Limits) -> Outcome {
+        self.reduce_counting(strategy, limits, None)
+    }
+
+    /// Like `reduce_with_limits`, but also reports what the reduction did and how long it
+    /// took.
+    pub fn reduce_with_stats(self, strategy: Strategy, limits: &Limits) -> (Outcome, EvalStats) {
+        let start = Instant::now();
+        let mut stats = EvalStats::default();
+        let outcome = self.reduce_counting(strategy, limits, Some(&mut stats));
+        stats.time = start.elapsed();
+        (outcome, stats)
+    }
+
+    fn reduce_counting(
+        mut self,
+        strategy: Strategy,
+        limits: &Limits,
+        stats: Option<&mut EvalStats>,
+    ) -> Outcome {
+        // Measuring walks the whole term, so sizes are only kept when something reads them.
+        let measure = stats.is_some() || limits.size.is_some();
+        let mut unused = EvalStats::default();
+        let stats = stats.unwrap_or(&mut unused);
 
-            _ => self,
+        if strategy == Strategy::CallByNeed {
+            return lazy::evaluate_counting(&self, limits, stats);
         }
+
+        // Only the contracted subterm changes, so the size is kept up to date from its size
+        // before and after.
+        let mut size = if measure { self.size() } else { 0 };
+        stats.peak_size = cmp::max(stats.peak_size, size);
+
+        let mut steps = 0;
+        while let Some(path) = self.redex(strategy) {
+            if limits.steps.map_or(false, |max| steps >= max) {
+                return Outcome::StepLimit(self);
+            }
+            if limits.is_cancelled() {
+                return Outcome::Cancelled(self);
+            }
+
+            {
+                let redex = self.subterm_mut(&path);
+                if measure {
+                    size -= redex.size();
+                }
+                redex.contract(stats);
+                if measure {
+                    size += redex.size();
+                }
+            }
+            steps += 1;
+            stats.peak_size = cmp::max(stats.peak_size, size);
+
+            if limits.size.map_or(false, |max| size > max) {
+                return Outcome::SizeLimit(self);
+            }
+        }
+        Outcome::Normal(self)
     }
 
-    fn shifted(self, d: isize, c: usize) -> Expression {
-        match self {
-            Expression::Variable { index: Some(index) } if index >= c => Expression::Variable {
-                index: Some((index as isize + d) as usize),
-            },
-            Expression::Variable { .. } => self,
+    /// The number of nodes in the term.
+    pub fn size(&self) -> usize {
+        let mut size = 0;
+        let mut stack = vec![self];
+        while let Some(expression) = stack.pop() {
+            size += 1;
+            match expression {
+                Expression::Variable { .. }
+                | Expression::Constant { .. }
+                | Expression::Integer { .. }
+                | Expression::Primitive { .. } => {}
+                Expression::Abstraction { expression, .. } => stack.push(expression),
+                Expression::Application {
+                    applicand,
+                    argument,
+                } => {
+                    stack.push(argument);
+                    stack.push(applicand);
+                }
+            }
+        }
+        size
+    }
 
-            Expression::Abstraction { expression } => Expression::Abstraction {
-                expression: box expression.shifted(d, c + 1),
-            },
+    /// Finds the path to the redex `strategy` reduces next.
+    fn redex(&self, strategy: Strategy) -> Option<Vec<Direction>> {
+        enum Task<'a> {
+            Visit(&'a Expression),
+            /// Applicative order only contracts an application after searching both sides.
+            Contract,
+            /// Visits the arguments in the applicand chain of a primitive application.
+            Arguments(&'a Expression),
+        }
 
-            Expression::Application {
-                applicand,
-                argument,
-            } => Expression::Application {
-                applicand: box applicand.shifted(d, c),
-                argument: box argument.shifted(d, c),
-            },
+        // Each task remembers how long the path to its parent was and which way it went.
+        let mut path = Vec::new();
+        let mut tasks = vec![(Task::Visit(self), 0, None)];
+
+        while let Some((task, length, direction)) = tasks.pop() {
+            path.truncate(length);
+            path.extend(direction);
+            let length = path.len();
+
+            let expression = match task {
+                Task::Visit(expression) => expression,
+                Task::Contract => return Some(path),
+                Task::Arguments(expression) => {
+                    if let Expression::Application {
+                        applicand,
+                        argument,
+                    } = expression
+                    {
+                        tasks.push((Task::Visit(argument), length, Some(Direction::Argument)));
+                        tasks.push((
+                            Task::Arguments(applicand),
+                            length,
+                            Some(Direction::Applicand),
+                        ));
+                    }
+                    continue;
+                }
+            };
+
+            match expression {
+                Expression::Variable { .. }
+                | Expression::Constant { .. }
+                | Expression::Integer { .. }
+                | Expression::Primitive { .. } => {}
+
+                Expression::Abstraction { expression, .. } => match strategy {
+                    Strategy::NormalOrder | Strategy::HeadNormalForm => {
+                        tasks.push((Task::Visit(expression), length, Some(Direction::Body)))
+                    }
+                    Strategy::ApplicativeOrder | Strategy::CallByName | Strategy::CallByNeed => {}
+                },
+
+                Expression::Application {
+                    applicand,
+                    argument,
+                } => {
+                    // Primitives need their arguments reduced to integers whatever the
+                    // strategy.
+                    if expression.primitive_application().is_some() {
+                        if expression.delta().is_some() {
+                            return Some(path);
+                        }
+                        tasks.push((Task::Arguments(expression), length, None));
+                        continue;
+                    }
+
+                    let is_redex = matches!(**applicand, Expression::Abstraction { .. });
+
+                    if strategy == Strategy::ApplicativeOrder {
+                        if is_redex {
+                            tasks.push((Task::Contract, length, None));
+                        }
+                        tasks.push((Task::Visit(argument), length, Some(Direction::Argument)));
+                        tasks.push((Task::Visit(applicand), length, Some(Direction::Applicand)));
+                        continue;
+                    }
+
+                    if is_redex {
+                        return Some(path);
+                    }
+
+                    if strategy == Strategy::NormalOrder {
+                        tasks.push((Task::Visit(argument), length, Some(Direction::Argument)));
+                    }
+                    tasks.push((Task::Visit(applicand), length, Some(Direction::Applicand)));
+                }
+            }
         }
+
+        None
     }
 
-    fn substituted(self, j: usize, term: Expression) -> Expression {
-        match self {
-            Expression::Variable { index: Some(index) } if index == j => term,
-            Expression::Variable { .. } => self,
+    /// Returns the subterm at `path`, if there is one.
+    pub fn subterm(&self, path: &[Direction]) -> Option<&Expression> {
+        let mut expression = self;
+        for direction in path {
+            expression = match (direction, expression) {
+                (Direction::Body, Expression::Abstraction { expression, .. }) => expression,
+                (Direction::Applicand, Expression::Application { applicand, .. }) => applicand,
+                (Direction::Argument, Expression::Application { argument, .. }) => argument,
+                _ => return None,
+            };
+        }
+        Some(expression)
+    }
 
-            Expression::Abstraction { expression } => Expression::Abstraction {
-                expression: box expression.substituted(j + 1, term.shifted(1, 0)),
-            },
+    fn subterm_mut(&mut self, path: &[Direction]) -> &mut Expression {
+        let mut expression = self;
+        for direction in path {
+            expression = match (direction, expression) {
+                (Direction::Body, Expression::Abstraction { expression, .. }) => expression,
+                (Direction::Applicand, Expression::Application { applicand, .. }) => applicand,
+                (Direction::Argument, Expression::Application { argument, .. }) => argument,
+                _ => panic!("no subterm at {:?}", path),
+            };
+        }
+        expression
+    }
 
+    /// Returns the primitive and its arguments if `self` applies one to as many arguments as
+    /// it takes.
+    fn primitive_application(&self) -> Option<(Primitive, Vec<&Expression>)> {
+        let mut arguments = Vec::new();
+        let mut expression = self;
+        while let Expression::Application {
+            applicand,
+            argument,
+        } = expression
+        {
+            arguments.push(&**argument);
+            expression = applicand;
+        }
+
+        match expression {
+            Expression::Primitive { primitive } if primitive.arity() == arguments.len() => {
+                arguments.reverse();
+                Some((*primitive, arguments))
+            }
+            _ => None,
+        }
+    }
+
+    /// Applies the primitive if `self` is a primitive application to integers that it is
+    /// defined for.
+    fn delta(&self) -> Option<primitive::Value> {
+        let (primitive, arguments) = self.primitive_application()?;
+        let integers = arguments
+            .iter()
+            .map(|argument| match argument {
+                Expression::Integer { value } => Some(value),
+                _ => None,
+            }).collect::<Option<Vec<_>>>()?;
+        primitive.apply(integers[0], integers[1])
+    }
+
+    /// Reduces the redex `self` in place.
+    fn contract(&mut self, stats: &mut EvalStats) {
+        if let Some(value) = self.delta() {
+            stats.delta_reductions += 1;
+            *self = Expression::from(value);
+            return;
+        }
+
+        let (body, argument) = match self {
             Expression::Application {
                 applicand,
                 argument,
-            } => Expression::Application {
-                applicand: box applicand.substituted(j, term.clone()),
-                argument: box argument.substituted(j, term),
+            } => match &mut **applicand {
+                Expression::Abstraction { name, expression } => {
+                    stats.reduction(name);
+                    (expression.take(), argument.take())
+                }
+                _ => panic!("not a redex"),
             },
+            _ => panic!("not a redex"),
+        };
+        *self = body.applied(argument, stats);
+    }
+
+    /// Substitutes `argument` for the variable bound by the abstraction whose body is `self`.
+    fn applied(self, argument: Expression, stats: &mut EvalStats) -> Expression {
+        let argument = argument.shifted(1, 0, stats);
+        self.substituted(0, argument, stats).shifted(-1, 0, stats)
+    }
+
+    fn shifted(mut self, d: isize, c: usize, stats: &mut EvalStats) -> Expression {
+        if d == 0 {
+            return self;
+        }
+        self.for_each_variable_mut(|variable, depth| {
+            if let Expression::Variable { index: Some(index) } = variable {
+                if *index >= c + depth {
+                    *index = (*index as isize + d) as usize;
+                    stats.shifts += 1;
+                }
+            }
+        });
+        self
+    }
+
+    fn substituted(mut self, j: usize, term: Expression, stats: &mut EvalStats) -> Expression {
+        self.for_each_variable_mut(|variable, depth| {
+            if let Expression::Variable { index: Some(index) } = variable {
+                if *index == j + depth {
+                    stats.substitutions += 1;
+                    *variable = term.clone().shifted(depth as isize, 0, stats);
+                }
+            }
+        });
+        self
+    }
+
+    /// Calls `f` with every leaf of the term, and the number of abstractions around it.
+    pub(crate) fn for_each_variable_mut<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&mut Expression, usize),
+    {
+        let mut stack = vec![(self, 0)];
+        while let Some((expression, depth)) = stack.pop() {
+            match expression {
+                Expression::Abstraction { expression, .. } => stack.push((expression, depth + 1)),
+                Expression::Application {
+                    applicand,
+                    argument,
+                } => {
+                    stack.push((argument, depth));
+                    stack.push((applicand, depth));
+                }
+                variable => f(variable, depth),
+            }
         }
     }
+
+    /// Moves the expression out, leaving a placeholder behind.
+    pub(crate) fn take(&mut self) -> Expression {
+        mem::replace(self, Expression::Variable { index: None })
+    }
 }
 
-impl Display for Expression {
-    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        match self {
-            Expression::Variable { index } => match index {
-                Some(index) => index.fmt(f),
-                None => f.write_str("None"),
-            },
+/// See `Expression::steps`.
+pub struct Steps {
+    expression: Option<Expression>,
+    strategy: Strategy,
+}
 
-            Expression::Abstraction { box expression } => match expression {
-                Expression::Variable { .. } => write!(f, r"\ {}", expression),
-                Expression::Abstraction { .. } => write!(f, r"\ {}", expression),
-                Expression::Application { .. } => write!(f, r"\ {}", expression),
-            },
+/// A term on the way to normal form.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Step {
+    pub expression: Expression,
+    /// Where the redex that is contracted next is, or `None` if this is the normal form.
+    pub redex: Option<Vec<Direction>>,
+}
 
-            Expression::Application {
-                box applicand,
-                box argument,
-            } => {
-                match applicand {
-                    Expression::Variable { .. } => applicand.fmt(f)?,
-                    Expression::Abstraction { .. } => write!(f, r"({})", applicand)?,
-                    Expression::Application { .. } => applicand.fmt(f)?,
+impl Iterator for Steps {
+    type Item = Step;
+
+    fn next(&mut self) -> Option<Step> {
+        let mut expression = self.expression.take()?;
+        let redex = expression.redex(self.strategy);
+
+        if let Some(ref path) = redex {
+            let current = expression.clone();
+            expression.subterm_mut(path).contract(&mut EvalStats::default());
+            self.expression = Some(expression);
+            expression = current;
+        }
+
+        Some(Step { expression, redex })
+    }
+}
+
+/// Which child of an expression to go into. A sequence of them from the root locates a
+/// subterm.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Direction {
+    Body,
+    Applicand,
+    Argument,
+}
+
+// Terms like large numerals nest far deeper than the call stack allows, so the traits below
+// walk them with explicit stacks instead of deriving recursive implementations. `Debug` is
+// still derived and should not be used on such terms.
+
+impl Clone for Expression {
+    fn clone(&self) -> Expression {
+        enum Task<'a> {
+            Clone(&'a Expression),
+            Abstraction(&'a Name),
+            Application,
+        }
+
+        let mut tasks = vec![Task::Clone(self)];
+        let mut results = Vec::new();
+
+        while let Some(task) = tasks.pop() {
+            match task {
+                Task::Clone(expression) => match expression {
+                    Expression::Variable { index } => {
+                        results.push(Expression::Variable { index: *index })
+                    }
+                    Expression::Constant { name } => {
+                        results.push(Expression::Constant { name: name.clone() })
+                    }
+                    Expression::Integer { value } => {
+                        results.push(Expression::Integer {
+                            value: value.clone(),
+                        })
+                    }
+                    Expression::Primitive { primitive } => results.push(Expression::Primitive {
+                        primitive: *primitive,
+                    }),
+                    Expression::Abstraction { name, expression } => {
+                        tasks.push(Task::Abstraction(name));
+                        tasks.push(Task::Clone(expression));
+                    }
+                    Expression::Application {
+                        applicand,
+                        argument,
+                    } => {
+                        tasks.push(Task::Application);
+                        tasks.push(Task::Clone(argument));
+                        tasks.push(Task::Clone(applicand));
+                    }
+                },
+
+                Task::Abstraction(name) => {
+                    let expression = results.pop().unwrap();
+                    results.push(Expression::Abstraction {
+                        name: name.clone(),
+                        expression: box expression,
+                    });
                 }
 
-                f.write_str(" ")?;
+                Task::Application => {
+                    let argument = results.pop().unwrap();
+                    let applicand = results.pop().unwrap();
+                    results.push(Expression::Application {
+                        applicand: box applicand,
+                        argument: box argument,
+                    });
+                }
+            }
+        }
 
-                match argument {
-                    Expression::Variable { .. } => argument.fmt(f),
-                    Expression::Abstraction { .. } => write!(f, r"({})", argument),
-                    Expression::Application { .. } => write!(f, r"({})", argument),
+        results.pop().unwrap()
+    }
+}
+
+impl PartialEq for Expression {
+    fn eq(&self, other: &Expression) -> bool {
+        let mut stack = vec![(self, other)];
+        while let Some(pair) = stack.pop() {
+            match pair {
+                (Expression::Variable { index: a }, Expression::Variable { index: b }) => {
+                    if a != b {
+                        return false;
+                    }
+                }
+                (Expression::Constant { name: a }, Expression::Constant { name: b }) => {
+                    if a != b {
+                        return false;
+                    }
+                }
+                (Expression::Integer { value: a }, Expression::Integer { value: b }) => {
+                    if a != b {
+                        return false;
+                    }
+                }
+                (
+                    Expression::Primitive { primitive: a },
+                    Expression::Primitive { primitive: b },
+                ) => {
+                    if a != b {
+                        return false;
+                    }
+                }
+                (
+                    Expression::Abstraction { expression: a, .. },
+                    Expression::Abstraction { expression: b, .. },
+                ) => stack.push((a, b)),
+                (
+                    Expression::Application {
+                        applicand: a,
+                        argument: x,
+                    },
+                    Expression::Application {
+                        applicand: b,
+                        argument: y,
+                    },
+                ) => {
+                    stack.push((x, y));
+                    stack.push((a, b));
+                }
+                _ => return false,
+            }
+        }
+        true
+    }
+}
+
+impl Drop for Expression {
+    fn drop(&mut self) {
+        /// Moves the children that have children of their own onto `stack`.
+        fn detach(expression: &mut Expression, stack: &mut Vec<Expression>) {
+            let mut push = |child: &mut Expression| match child {
+                Expression::Abstraction { .. } | Expression::Application { .. } => {
+                    stack.push(child.take())
+                }
+                _ => {}
+            };
+            match expression {
+                Expression::Variable { .. }
+                | Expression::Constant { .. }
+                | Expression::Integer { .. }
+                | Expression::Primitive { .. } => {}
+                Expression::Abstraction { expression, .. } => push(expression),
+                Expression::Application {
+                    applicand,
+                    argument,
+                } => {
+                    push(applicand);
+                    push(argument);
                 }
             }
         }
+
+        let mut stack = Vec::new();
+        detach(self, &mut stack);
+        while let Some(mut expression) = stack.pop() {
+            detach(&mut expression, &mut stack);
+        }
+    }
+}
+
+/// Church booleans for the primitives that compare.
+impl From<primitive::Value> for Expression {
+    fn from(value: primitive::Value) -> Expression {
+        match value {
+            primitive::Value::Integer(value) => Expression::Integer { value },
+            primitive::Value::Boolean(value) => Expression::Abstraction {
+                name: Name::from("x"),
+                expression: box Expression::Abstraction {
+                    name: Name::from("y"),
+                    expression: box Expression::Variable {
+                        index: Some(if value { 1 } else { 0 }),
+                    },
+                },
+            },
+        }
+    }
+}
+
+impl Display for Expression {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        self.display(Notation::Named).fmt(f)
     }
 }
 
@@ -307,31 +1393,37 @@ mod test {
         AbstractionExpression, ApplicationExpression, Expression as CSTExpression,
         ExpressionStatement, Identifier, LetStatement, Program, Statement, VariableExpression,
     };
+    use token::Span;
 
     #[test]
     fn test_shift() {
+        let stats = &mut EvalStats::default();
         let expected = Expression::Variable { index: Some(1) };
-        let result = Expression::Variable { index: Some(0) }.shifted(1, 0);
+        let result = Expression::Variable { index: Some(0) }.shifted(1, 0, stats);
         assert_eq!(expected, result);
 
         let expected = Expression::Variable { index: Some(0) };
-        let result = Expression::Variable { index: Some(0) }.shifted(1, 1);
+        let result = Expression::Variable { index: Some(0) }.shifted(1, 1, stats);
         assert_eq!(expected, result);
 
         let expected = Expression::Abstraction {
+            name: Name::default(),
             expression: box Expression::Variable { index: Some(2) },
         };
         let result = Expression::Abstraction {
+            name: Name::default(),
             expression: box Expression::Variable { index: Some(1) },
-        }.shifted(1, 0);
+        }.shifted(1, 0, stats);
         assert_eq!(expected, result);
 
         let expected = Expression::Abstraction {
+            name: Name::default(),
             expression: box Expression::Variable { index: Some(0) },
         };
         let result = Expression::Abstraction {
+            name: Name::default(),
             expression: box Expression::Variable { index: Some(0) },
-        }.shifted(1, 0);
+        }.shifted(1, 0, stats);
         assert_eq!(expected, result);
 
         let expected = Expression::Application {
@@ -341,36 +1433,41 @@ mod test {
         let result = Expression::Application {
             applicand: box Expression::Variable { index: Some(0) },
             argument: box Expression::Variable { index: Some(1) },
-        }.shifted(1, 0);
+        }.shifted(1, 0, stats);
         assert_eq!(expected, result);
     }
 
     #[test]
     fn test_substitute() {
+        let stats = &mut EvalStats::default();
         let expected = Expression::Variable { index: None };
         let result = Expression::Variable { index: Some(0) }
-            .substituted(0, Expression::Variable { index: None });
+            .substituted(0, Expression::Variable { index: None }, stats);
         assert_eq!(expected, result);
 
         let expected = Expression::Variable { index: Some(1) };
         let result = Expression::Variable { index: Some(1) }
-            .substituted(0, Expression::Variable { index: None });
+            .substituted(0, Expression::Variable { index: None }, stats);
         assert_eq!(expected, result);
 
         let expected = Expression::Abstraction {
+            name: Name::default(),
             expression: box Expression::Variable { index: None },
         };
         let result = Expression::Abstraction {
+            name: Name::default(),
             expression: box Expression::Variable { index: Some(1) },
-        }.substituted(0, Expression::Variable { index: None });
+        }.substituted(0, Expression::Variable { index: None }, stats);
         assert_eq!(expected, result);
 
         let expected = Expression::Abstraction {
+            name: Name::default(),
             expression: box Expression::Variable { index: Some(0) },
         };
         let result = Expression::Abstraction {
+            name: Name::default(),
             expression: box Expression::Variable { index: Some(0) },
-        }.substituted(0, Expression::Variable { index: None });
+        }.substituted(0, Expression::Variable { index: None }, stats);
         assert_eq!(expected, result);
 
         let expected = Expression::Application {
@@ -380,7 +1477,7 @@ mod test {
         let result = Expression::Application {
             applicand: box Expression::Variable { index: Some(0) },
             argument: box Expression::Variable { index: Some(1) },
-        }.substituted(1, Expression::Variable { index: None });
+        }.substituted(1, Expression::Variable { index: None }, stats);
         assert_eq!(expected, result);
     }
 
@@ -388,14 +1485,21 @@ mod test {
     fn translate_abstraction() {
         let result = Expression::from_cst_expression(
             &CSTExpression::from(AbstractionExpression::new(
-                vec![Identifier::new("x"), Identifier::new("x")],
-                VariableExpression::new(Identifier::new("x")),
+                vec![
+                    Identifier::new("x", Span::default()),
+                    Identifier::new("x", Span::default()),
+                ],
+                VariableExpression::new(Identifier::new("x", Span::default())),
+                Span::default(),
             )),
             &mut Vec::new(),
+            &Options::default(),
         );
 
         let expected = Expression::Abstraction {
+            name: Name::default(),
             expression: box Expression::Abstraction {
+                name: Name::default(),
                 expression: box Expression::Variable { index: Some(0) },
             },
         };
@@ -403,20 +1507,31 @@ mod test {
 
         let b = Expression::from_cst_expression(
             &CSTExpression::from(AbstractionExpression::new(
-                vec![Identifier::new("x")],
-                ApplicationExpression::new(vec![
-                    CSTExpression::from(AbstractionExpression::new(
-                        vec![Identifier::new("x")],
-                        VariableExpression::new(Identifier::new("x")),
-                    )),
-                    CSTExpression::from(VariableExpression::new(Identifier::new("x"))),
-                ]),
+                vec![Identifier::new("x", Span::default())],
+                ApplicationExpression::new(
+                    vec![
+                        CSTExpression::from(AbstractionExpression::new(
+                            vec![Identifier::new("x", Span::default())],
+                            VariableExpression::new(Identifier::new("x", Span::default())),
+                            Span::default(),
+                        )),
+                        CSTExpression::from(VariableExpression::new(Identifier::new(
+                            "x",
+                            Span::default(),
+                        ))),
+                    ],
+                    Span::default(),
+                ),
+                Span::default(),
             )),
             &mut Vec::new(),
+            &Options::default(),
         );
         let expected = Expression::Abstraction {
+            name: Name::default(),
             expression: box Expression::Application {
                 applicand: box Expression::Abstraction {
+                    name: Name::default(),
                     expression: box Expression::Variable { index: Some(0) },
                 },
                 argument: box Expression::Variable { index: Some(0) },
@@ -428,12 +1543,25 @@ mod test {
     #[test]
     fn translate_application() {
         let a = Expression::from_cst_expression(
-            &CSTExpression::from(ApplicationExpression::new(vec![
-                CSTExpression::from(VariableExpression::new(Identifier::new("a"))),
-                CSTExpression::from(VariableExpression::new(Identifier::new("b"))),
-                CSTExpression::from(VariableExpression::new(Identifier::new("c"))),
-            ])),
+            &CSTExpression::from(ApplicationExpression::new(
+                vec![
+                    CSTExpression::from(VariableExpression::new(Identifier::new(
+                        "a",
+                        Span::default(),
+                    ))),
+                    CSTExpression::from(VariableExpression::new(Identifier::new(
+                        "b",
+                        Span::default(),
+                    ))),
+                    CSTExpression::from(VariableExpression::new(Identifier::new(
+                        "c",
+                        Span::default(),
+                    ))),
+                ],
+                Span::default(),
+            )),
             &mut Vec::new(),
+            &Options::default(),
         );
         let expected = Expression::Application {
             applicand: box Expression::Application {
@@ -445,30 +1573,240 @@ mod test {
         assert_eq!(expected, a);
     }
 
+    #[test]
+    fn translate_open_term() {
+        let result = Expression::from_cst_expression(
+            &CSTExpression::from(AbstractionExpression::new(
+                vec![Identifier::new("x", Span::default())],
+                ApplicationExpression::new(
+                    vec![
+                        CSTExpression::from(VariableExpression::new(Identifier::new(
+                            "f",
+                            Span::default(),
+                        ))),
+                        CSTExpression::from(VariableExpression::new(Identifier::new(
+                            "x",
+                            Span::default(),
+                        ))),
+                    ],
+                    Span::default(),
+                ),
+                Span::default(),
+            )),
+            &mut Vec::new(),
+            &Options {
+                open_terms: true,
+                ..Options::default()
+            },
+        );
+        let expected = Expression::Abstraction {
+            name: Name::default(),
+            expression: box Expression::Application {
+                applicand: box Expression::Constant {
+                    name: "f".to_owned(),
+                },
+                argument: box Expression::Variable { index: Some(0) },
+            },
+        };
+        assert_eq!(expected, result);
+    }
+
     #[test]
     fn translate_let_statement() {
         let expected = Expression::Application {
             applicand: box Expression::Abstraction {
+                name: Name::default(),
                 expression: box Expression::Variable { index: Some(0) },
             },
             argument: box Expression::Abstraction {
+                name: Name::default(),
                 expression: box Expression::Variable { index: Some(0) },
             },
         };
         let result = Expression::from_cst_program(&Program(vec![
             Statement::from(LetStatement::new(
-                Identifier::new("id"),
+                Identifier::new("id", Span::default()),
                 CSTExpression::from(AbstractionExpression::new(
-                    vec![Identifier::new("x")],
-                    CSTExpression::from(ApplicationExpression::new(vec![CSTExpression::from(
-                        VariableExpression::new(Identifier::new("x")),
-                    )])),
+                    vec![Identifier::new("x", Span::default())],
+                    CSTExpression::from(ApplicationExpression::new(
+                        vec![CSTExpression::from(VariableExpression::new(
+                            Identifier::new("x", Span::default()),
+                        ))],
+                        Span::default(),
+                    )),
+                    Span::default(),
                 )),
+                Span::default(),
             )),
             Statement::from(ExpressionStatement::new(CSTExpression::from(
-                VariableExpression::new(Identifier::new("id")),
+                VariableExpression::new(Identifier::new("id", Span::default())),
             ))),
         ]));
-        assert_eq!(expected, result);
+        assert_eq!(Ok(expected), result);
+    }
+
+    #[test]
+    fn translate_missing_expression() {
+        let source = r"let x = \x. x; let y = ;";
+        let (program, errors) = parser::parse_with_recovery(&mut Lexer::new(source));
+        assert_eq!(1, errors.len());
+        let error = Expression::from_cst_program(&program).unwrap_err();
+        assert_eq!(
+            TranslationError::MissingExpression {
+                span: program.0.last().unwrap().span()
+            },
+            error
+        );
+        assert_eq!(
+            Err(TranslationError::MissingExpression {
+                span: Span::default()
+            }),
+            Expression::from_cst_program(&Program(Vec::new()))
+        );
+    }
+
+    #[test]
+    fn translate_let_expression() {
+        let translate = |source| {
+            Expression::from_cst_program(&parser::parse(&mut Lexer::new(source)).unwrap()).unwrap()
+        };
+        assert_eq!(
+            translate(r"(\x. (\y. y x) x) (\a. a);"),
+            translate(r"let x = \a. a, y = x in y x;")
+        );
+        // A binding sees the ones before it, but not itself.
+        assert_eq!(translate(r"\x. (\x. x) x;"), translate(r"\x. let x = x in x;"));
+    }
+
+    #[test]
+    fn translate_let_rec() {
+        let translate = |source: &str| {
+            Expression::from_cst_program(&parser::parse(&mut Lexer::new(source)).unwrap()).unwrap()
+        };
+        let z = r"(\f. (\x. f (\v. x x v)) (\x. f (\v. x x v)))";
+        assert_eq!(
+            translate(&format!(r"(\f. f) ({} (\f x. f x));", z)),
+            translate(r"let rec f = \x. f x; f;")
+        );
+        assert_eq!(
+            translate(&format!(r"\a. (\f. f) ({} (\f. a f));", z)),
+            translate(r"\a. let rec f = a f in f;")
+        );
+
+        let (f, g) = (r"(\v. t (\x y. x) v)", r"(\v. t (\x y. y) v)");
+        let group = format!(
+            r"(\F G. {0} (\t s. s (F {1} {2}) (G {1} {2}))) (\f g. g) (\f g. f)",
+            z, f, g
+        );
+        assert_eq!(
+            translate(&format!(r"{} (\f g. g);", group)),
+            translate(r"let rec f = g and g = f; g;")
+        );
+    }
+
+    #[test]
+    fn translate_character() {
+        let character = |c: char| {
+            Expression::from_cst_expression(
+                &CSTExpression::from(Character::new(c, Span::default())),
+                &mut Vec::new(),
+                &Options::default(),
+            )
+        };
+        let number = |n| Expression::church(n);
+
+        assert_eq!(number(97), character('a'));
+        assert_ne!(number(0x1F600), character('\u{1F600}'));
+        assert_eq!(number(256), character('\u{100}').normalize());
+        assert_eq!(number(0x1234), character('\u{1234}').normalize());
+    }
+
+    #[test]
+    fn test_normalize() {
+        // (\x. \y. x y) (\z. z)
+        let result = Expression::Application {
+            applicand: box Expression::Abstraction {
+                name: Name::default(),
+                expression: box Expression::Abstraction {
+                    name: Name::default(),
+                    expression: box Expression::Application {
+                        applicand: box Expression::Variable { index: Some(1) },
+                        argument: box Expression::Variable { index: Some(0) },
+                    },
+                },
+            },
+            argument: box Expression::Abstraction {
+                name: Name::default(),
+                expression: box Expression::Variable { index: Some(0) },
+            },
+        };
+        let expected = Expression::Abstraction {
+            name: Name::default(),
+            expression: box Expression::Variable { index: Some(0) },
+        };
+        assert_ne!(expected, result.clone().evaluate());
+        assert_eq!(expected, result.normalize());
+    }
+
+    #[test]
+    fn test_evaluate_with_limits() {
+        // (\x. x x) (\x. x x)
+        let omega = Expression::Abstraction {
+            name: Name::default(),
+            expression: box Expression::Application {
+                applicand: box Expression::Variable { index: Some(0) },
+                argument: box Expression::Variable { index: Some(0) },
+            },
+        };
+        let divergent = Expression::Application {
+            applicand: box omega.clone(),
+            argument: box omega.clone(),
+        };
+
+        let limits = Limits {
+            steps: Some(10),
+            ..Limits::default()
+        };
+        assert_eq!(
+            Outcome::StepLimit(divergent.clone()),
+            divergent.clone().evaluate_with_limits(&limits)
+        );
+        assert_eq!(
+            Outcome::Normal(omega.clone()),
+            omega.clone().evaluate_with_limits(&limits)
+        );
+
+        let limits = Limits {
+            cancelled: Some(&|| true),
+            ..Limits::default()
+        };
+        assert_eq!(
+            Outcome::Cancelled(divergent.clone()),
+            divergent.clone().evaluate_with_limits(&limits)
+        );
+
+        // (\x. x x x) (\x. x x x)
+        let omega3 = Expression::Abstraction {
+            name: Name::default(),
+            expression: box Expression::Application {
+                applicand: box Expression::Application {
+                    applicand: box Expression::Variable { index: Some(0) },
+                    argument: box Expression::Variable { index: Some(0) },
+                },
+                argument: box Expression::Variable { index: Some(0) },
+            },
+        };
+        let growing = Expression::Application {
+            applicand: box omega3.clone(),
+            argument: box omega3.clone(),
+        };
+        let limits = Limits {
+            size: Some(100),
+            ..Limits::default()
+        };
+        match growing.evaluate_with_limits(&limits) {
+            Outcome::SizeLimit(expression) => assert!(expression.size() > 100),
+            outcome => panic!("{:?}", outcome),
+        }
     }
 }
@@ -1,13 +1,16 @@
 use cst::{Expression, Identifier};
+use token::{Span, Token};
 
 #[derive(Debug, PartialEq)]
 pub struct AbstractionExpression {
     pub parameters: Vec<Identifier>,
     pub expression: Box<Expression>,
+    pub span: Span,
+    pub tokens: Vec<Token>,
 }
 
 impl AbstractionExpression {
-    pub fn new<T, U>(parameters: T, expression: U) -> AbstractionExpression
+    pub fn new<T, U>(parameters: T, expression: U, span: Span) -> AbstractionExpression
     where
         T: Into<Vec<Identifier>>,
         U: Into<Expression>,
@@ -15,6 +18,8 @@ impl AbstractionExpression {
         AbstractionExpression {
             parameters: parameters.into(),
             expression: box expression.into(),
+            span,
+            tokens: Vec::new(),
         }
     }
 }
@@ -1,17 +1,22 @@
 use cst::Expression;
+use token::{Span, Token};
 
 #[derive(Debug, PartialEq)]
 pub struct ApplicationExpression {
     pub expressions: Vec<Expression>,
+    pub span: Span,
+    pub tokens: Vec<Token>,
 }
 
 impl ApplicationExpression {
-    pub fn new<T>(expressions: T) -> ApplicationExpression
+    pub fn new<T>(expressions: T, span: Span) -> ApplicationExpression
     where
         T: Into<Vec<Expression>>,
     {
         ApplicationExpression {
             expressions: expressions.into(),
+            span,
+            tokens: Vec::new(),
         }
     }
 }
@@ -0,0 +1,55 @@
+use cst::{Expression, Identifier};
+use token::{Span, Token};
+
+/// `let x = a, y = b in c`, where each binding can refer to the ones before it, or
+/// `let rec x = a and y = b in c`, where every binding can refer to all of them.
+#[derive(Debug, PartialEq)]
+pub struct LetExpression {
+    pub bindings: Vec<Binding>,
+    pub expression: Box<Expression>,
+    pub recursive: bool,
+    pub span: Span,
+    pub tokens: Vec<Token>,
+}
+
+impl LetExpression {
+    pub fn new<T, U>(bindings: T, expression: U, span: Span) -> LetExpression
+    where
+        T: Into<Vec<Binding>>,
+        U: Into<Expression>,
+    {
+        LetExpression {
+            bindings: bindings.into(),
+            expression: box expression.into(),
+            recursive: false,
+            span,
+            tokens: Vec::new(),
+        }
+    }
+}
+
+/// The `x = a` of a `let` expression or a `let rec` statement.
+#[derive(Debug, PartialEq)]
+pub struct Binding {
+    pub variable: Identifier,
+    pub expression: Expression,
+    pub span: Span,
+    pub tokens: Vec<Token>,
+}
+
+impl Binding {
+    pub fn new<T, U>(variable: T, expression: U) -> Binding
+    where
+        T: Into<Identifier>,
+        U: Into<Expression>,
+    {
+        let variable = variable.into();
+        let expression = expression.into();
+        Binding {
+            span: variable.span.to(expression.span()),
+            variable,
+            expression,
+            tokens: Vec::new(),
+        }
+    }
+}
@@ -1,18 +1,37 @@
 mod abstraction;
 mod application;
+mod let_e;
 mod variable;
 pub use self::abstraction::*;
 pub use self::application::*;
+pub use self::let_e::*;
 pub use self::variable::*;
-use cst::{Character, Number};
+use cst::{Character, Number, StringLiteral};
+use token::Span;
 
 #[derive(Debug, PartialEq)]
 pub enum Expression {
     Variable(VariableExpression),
     Abstraction(AbstractionExpression),
     Application(ApplicationExpression),
+    Let(LetExpression),
     Number(Number),
     Character(Character),
+    String(StringLiteral),
+}
+
+impl Expression {
+    pub fn span(&self) -> Span {
+        match self {
+            Expression::Variable(variable) => variable.span,
+            Expression::Abstraction(abstraction) => abstraction.span,
+            Expression::Application(application) => application.span,
+            Expression::Let(let_expression) => let_expression.span,
+            Expression::Number(number) => number.span,
+            Expression::Character(character) => character.span,
+            Expression::String(string) => string.span,
+        }
+    }
 }
 
 impl From<VariableExpression> for Expression {
@@ -33,6 +52,12 @@ impl From<ApplicationExpression> for Expression {
     }
 }
 
+impl From<LetExpression> for Expression {
+    fn from(let_expression: LetExpression) -> Expression {
+        Expression::Let(let_expression)
+    }
+}
+
 impl From<Number> for Expression {
     fn from(number: Number) -> Expression {
         Expression::Number(number)
@@ -44,3 +69,9 @@ impl From<Character> for Expression {
         Expression::Character(character)
     }
 }
+
+impl From<StringLiteral> for Expression {
+    fn from(string: StringLiteral) -> Expression {
+        Expression::String(string)
+    }
+}
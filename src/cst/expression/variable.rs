@@ -1,8 +1,10 @@
 use cst::Identifier;
+use token::Span;
 
 #[derive(Debug, PartialEq)]
 pub struct VariableExpression {
     pub identifier: Identifier,
+    pub span: Span,
 }
 
 impl VariableExpression {
@@ -10,8 +12,10 @@ impl VariableExpression {
     where
         T: Into<Identifier>,
     {
+        let identifier = identifier.into();
         VariableExpression {
-            identifier: identifier.into(),
+            span: identifier.span,
+            identifier,
         }
     }
 }
@@ -4,39 +4,85 @@ mod statement;
 pub use self::expression::*;
 pub use self::program::*;
 pub use self::statement::*;
+use token::{Span, Token};
 
 #[derive(Debug, PartialEq)]
-pub struct Identifier(pub String);
+pub struct Identifier {
+    pub name: String,
+    pub span: Span,
+    pub tokens: Vec<Token>,
+}
 
 impl Identifier {
-    pub fn new<T>(value: T) -> Identifier
+    pub fn new<T>(name: T, span: Span) -> Identifier
     where
         T: Into<String>,
     {
-        Identifier(value.into())
+        Identifier {
+            name: name.into(),
+            span,
+            tokens: Vec::new(),
+        }
     }
 }
 
 #[derive(Debug, PartialEq)]
-pub struct Number(pub String);
+pub struct Number {
+    pub value: String,
+    pub span: Span,
+    pub tokens: Vec<Token>,
+}
 
 impl Number {
-    pub fn new<T>(value: T) -> Number
+    pub fn new<T>(value: T, span: Span) -> Number
     where
         T: Into<String>,
     {
-        Number(value.into())
+        Number {
+            value: value.into(),
+            span,
+            tokens: Vec::new(),
+        }
     }
 }
 
 #[derive(Debug, PartialEq)]
-pub struct Character(pub char);
+pub struct Character {
+    pub value: char,
+    pub span: Span,
+    pub tokens: Vec<Token>,
+}
 
 impl Character {
-    pub fn new<T>(value: T) -> Character
+    pub fn new<T>(value: T, span: Span) -> Character
     where
         T: Into<char>,
     {
-        Character(value.into())
+        Character {
+            value: value.into(),
+            span,
+            tokens: Vec::new(),
+        }
+    }
+}
+
+/// A string literal, with its escape sequences already replaced.
+#[derive(Debug, PartialEq)]
+pub struct StringLiteral {
+    pub value: String,
+    pub span: Span,
+    pub tokens: Vec<Token>,
+}
+
+impl StringLiteral {
+    pub fn new<T>(value: T, span: Span) -> StringLiteral
+    where
+        T: Into<String>,
+    {
+        StringLiteral {
+            value: value.into(),
+            span,
+            tokens: Vec::new(),
+        }
     }
 }
@@ -1,4 +1,82 @@
-use cst::Statement;
+use cst::{Binding, Expression, Statement};
+use token::Token;
 
 #[derive(Debug, PartialEq)]
 pub struct Program(pub Vec<Statement>);
+
+impl Program {
+    /// Prints a program parsed from `Lexer::lossless` back to its source, byte for byte, even if
+    /// it has syntax errors. Each node's `tokens` hold the tokens of the node itself, not of its
+    /// children, with the trivia before them; other lexers leave them empty.
+    pub fn source(&self) -> String {
+        let Program(statements) = self;
+        let mut tokens = Vec::new();
+        for statement in statements {
+            statement_tokens(statement, &mut tokens);
+        }
+        tokens.sort_by_key(|token| token.span.start.offset);
+
+        let mut source = String::new();
+        for token in tokens {
+            for trivia in &token.trivia {
+                source.push_str(trivia.text.as_ref().map_or("", String::as_str));
+            }
+            source.push_str(token.text.as_ref().map_or("", String::as_str));
+        }
+        source
+    }
+}
+
+fn statement_tokens<'a>(statement: &'a Statement, tokens: &mut Vec<&'a Token>) {
+    tokens.extend(statement.tokens());
+    match statement {
+        Statement::Expression(expression_statement) => {
+            expression_tokens(&expression_statement.expression, tokens)
+        }
+        Statement::Let(let_statement) => {
+            tokens.extend(&let_statement.variable.tokens);
+            expression_tokens(&let_statement.expression, tokens);
+        }
+        Statement::LetRec(let_rec_statement) => {
+            for binding in &let_rec_statement.bindings {
+                binding_tokens(binding, tokens);
+            }
+        }
+        Statement::Error(..) => (),
+    }
+}
+
+fn expression_tokens<'a>(expression: &'a Expression, tokens: &mut Vec<&'a Token>) {
+    match expression {
+        Expression::Variable(variable) => tokens.extend(&variable.identifier.tokens),
+        Expression::Abstraction(abstraction) => {
+            tokens.extend(&abstraction.tokens);
+            for parameter in &abstraction.parameters {
+                tokens.extend(&parameter.tokens);
+            }
+            expression_tokens(&abstraction.expression, tokens);
+        }
+        Expression::Application(application) => {
+            tokens.extend(&application.tokens);
+            for expression in &application.expressions {
+                expression_tokens(expression, tokens);
+            }
+        }
+        Expression::Let(let_expression) => {
+            tokens.extend(&let_expression.tokens);
+            for binding in &let_expression.bindings {
+                binding_tokens(binding, tokens);
+            }
+            expression_tokens(&let_expression.expression, tokens);
+        }
+        Expression::Number(number) => tokens.extend(&number.tokens),
+        Expression::Character(character) => tokens.extend(&character.tokens),
+        Expression::String(string) => tokens.extend(&string.tokens),
+    }
+}
+
+fn binding_tokens<'a>(binding: &'a Binding, tokens: &mut Vec<&'a Token>) {
+    tokens.extend(&binding.variable.tokens);
+    tokens.extend(&binding.tokens);
+    expression_tokens(&binding.expression, tokens);
+}
@@ -0,0 +1,18 @@
+use token::{Span, Token};
+
+/// Stands in for a statement that failed to parse, covering the source that was skipped.
+#[derive(Debug, PartialEq)]
+pub struct ErrorStatement {
+    pub span: Span,
+    /// The tokens of the failed statement and those skipped after it.
+    pub tokens: Vec<Token>,
+}
+
+impl ErrorStatement {
+    pub fn new(span: Span) -> ErrorStatement {
+        ErrorStatement {
+            span,
+            tokens: Vec::new(),
+        }
+    }
+}
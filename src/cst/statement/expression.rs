@@ -1,8 +1,11 @@
 use cst::Expression;
+use token::{Span, Token};
 
 #[derive(Debug, PartialEq)]
 pub struct ExpressionStatement {
     pub expression: Expression,
+    pub span: Span,
+    pub tokens: Vec<Token>,
 }
 
 impl ExpressionStatement {
@@ -10,8 +13,11 @@ impl ExpressionStatement {
     where
         T: Into<Expression>,
     {
+        let expression = expression.into();
         ExpressionStatement {
-            expression: expression.into(),
+            span: expression.span(),
+            expression,
+            tokens: Vec::new(),
         }
     }
 }
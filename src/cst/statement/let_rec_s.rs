@@ -0,0 +1,26 @@
+use cst::Binding;
+use token::{Span, Token};
+
+/// `let rec x = a and y = b`, where every binding can refer to all of them.
+#[derive(Debug, PartialEq)]
+pub struct LetRecStatement {
+    pub bindings: Vec<Binding>,
+    pub span: Span,
+    /// The `///` doc comment written above the statement.
+    pub doc: Option<String>,
+    pub tokens: Vec<Token>,
+}
+
+impl LetRecStatement {
+    pub fn new<T>(bindings: T, span: Span) -> LetRecStatement
+    where
+        T: Into<Vec<Binding>>,
+    {
+        LetRecStatement {
+            bindings: bindings.into(),
+            span,
+            doc: None,
+            tokens: Vec::new(),
+        }
+    }
+}
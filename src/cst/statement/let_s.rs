@@ -1,13 +1,18 @@
 use cst::{Expression, Identifier};
+use token::{Span, Token};
 
 #[derive(Debug, PartialEq)]
 pub struct LetStatement {
     pub variable: Identifier,
     pub expression: Expression,
+    pub span: Span,
+    /// The `///` doc comment written above the statement.
+    pub doc: Option<String>,
+    pub tokens: Vec<Token>,
 }
 
 impl LetStatement {
-    pub fn new<T, U>(variable: T, expression: U) -> LetStatement
+    pub fn new<T, U>(variable: T, expression: U, span: Span) -> LetStatement
     where
         T: Into<Identifier>,
         U: Into<Expression>,
@@ -15,6 +20,9 @@ impl LetStatement {
         LetStatement {
             variable: variable.into(),
             expression: expression.into(),
+            span,
+            doc: None,
+            tokens: Vec::new(),
         }
     }
 }
@@ -1,12 +1,48 @@
+mod error;
 mod expression;
+mod let_rec_s;
 mod let_s;
+pub use self::error::*;
 pub use self::expression::*;
+pub use self::let_rec_s::*;
 pub use self::let_s::*;
+use token::{Span, Token};
 
 #[derive(Debug, PartialEq)]
 pub enum Statement {
     Expression(ExpressionStatement),
     Let(LetStatement),
+    LetRec(LetRecStatement),
+    Error(ErrorStatement),
+}
+
+impl Statement {
+    pub fn span(&self) -> Span {
+        match self {
+            Statement::Expression(expression_statement) => expression_statement.span,
+            Statement::Let(let_statement) => let_statement.span,
+            Statement::LetRec(let_rec_statement) => let_rec_statement.span,
+            Statement::Error(error_statement) => error_statement.span,
+        }
+    }
+
+    pub fn tokens(&self) -> &[Token] {
+        match self {
+            Statement::Expression(expression_statement) => &expression_statement.tokens,
+            Statement::Let(let_statement) => &let_statement.tokens,
+            Statement::LetRec(let_rec_statement) => &let_rec_statement.tokens,
+            Statement::Error(error_statement) => &error_statement.tokens,
+        }
+    }
+
+    pub fn tokens_mut(&mut self) -> &mut Vec<Token> {
+        match self {
+            Statement::Expression(expression_statement) => &mut expression_statement.tokens,
+            Statement::Let(let_statement) => &mut let_statement.tokens,
+            Statement::LetRec(let_rec_statement) => &mut let_rec_statement.tokens,
+            Statement::Error(error_statement) => &mut error_statement.tokens,
+        }
+    }
 }
 
 impl From<ExpressionStatement> for Statement {
@@ -20,3 +56,15 @@ impl From<LetStatement> for Statement {
         Statement::Let(let_statement)
     }
 }
+
+impl From<LetRecStatement> for Statement {
+    fn from(let_rec_statement: LetRecStatement) -> Statement {
+        Statement::LetRec(let_rec_statement)
+    }
+}
+
+impl From<ErrorStatement> for Statement {
+    fn from(error_statement: ErrorStatement) -> Statement {
+        Statement::Error(error_statement)
+    }
+}
@@ -0,0 +1,469 @@
+use ast::{Expression, Limits, Outcome};
+use lazy;
+use natural::Natural;
+use primitive::Primitive;
+use std::convert::TryFrom;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// The shape a term is expected to encode.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Type {
+    /// `\f x. f (f x)`
+    Number,
+    /// `\x y. x` and `\x y. y`
+    Boolean,
+    /// A number holding a Unicode scalar value.
+    Character,
+    /// Right folds: `\c n. c x (c y n)`
+    List(Box<Type>),
+    /// `\n c. c x (\n c. c y (\n c. n))`
+    ScottList(Box<Type>),
+    /// `\p. p x y`
+    Pair(Box<Type>, Box<Type>),
+    /// A list of characters in either encoding, as string literals desugar to.
+    String,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Value {
+    Number(Natural),
+    Boolean(bool),
+    Character(char),
+    List(Vec<Value>),
+    Pair(Box<Value>, Box<Value>),
+    String(String),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct InvalidType(pub String);
+
+/// Decodes `expression` as a value of type `ty`, or returns `None` if it does not have that
+/// shape.
+///
+/// Only the parts of the term that are needed get evaluated, so a weak head normal form like
+/// `\f x. f ((\f x. f x) f x)` decodes as well as `\f x. f (f x)`. Each of those evaluations
+/// is bounded by `limits`, and exceeding them also returns `None`.
+pub fn decode(expression: &Expression, ty: &Type, limits: &Limits) -> Option<Value> {
+    match ty {
+        Type::Number => natural(expression, limits).map(Value::Number),
+        Type::Boolean => boolean(expression, limits).map(Value::Boolean),
+        Type::Character => character(expression, limits).map(Value::Character),
+        Type::List(ty) => list(expression, limits)?
+            .iter()
+            .map(|element| decode(element, ty, limits))
+            .collect::<Option<_>>()
+            .map(Value::List),
+        Type::ScottList(ty) => scott_list(expression, limits)?
+            .iter()
+            .map(|element| decode(element, ty, limits))
+            .collect::<Option<_>>()
+            .map(Value::List),
+        Type::Pair(first_ty, second_ty) => {
+            let (first, second) = pair(expression, limits)?;
+            Some(Value::Pair(
+                box decode(&first, first_ty, limits)?,
+                box decode(&second, second_ty, limits)?,
+            ))
+        }
+        Type::String => string(expression, limits).map(Value::String),
+    }
+}
+
+/// Decodes a Church numeral, or an integer constant when primitives are enabled.
+pub fn natural(expression: &Expression, limits: &Limits) -> Option<Natural> {
+    let expression = evaluate(expression.clone(), limits)?;
+    if let Expression::Integer { value } = &expression {
+        return Some(value.clone());
+    }
+
+    binders(&expression, 2, limits)?;
+
+    // Counting with a successor primitive under call by need shares the work that walking the
+    // body would repeat for numerals built from smaller ones, like large characters.
+    let successor = Expression::Application {
+        applicand: box Expression::Primitive {
+            primitive: Primitive::Add,
+        },
+        argument: box Expression::Integer {
+            value: Natural::from(1),
+        },
+    };
+    let counted = Expression::Application {
+        applicand: box Expression::Application {
+            applicand: box expression,
+            argument: box successor,
+        },
+        argument: box Expression::Integer {
+            value: Natural::zero(),
+        },
+    };
+    match lazy::evaluate_with_limits(&counted, limits) {
+        Outcome::Normal(Expression::Integer { ref value }) => Some(value.clone()),
+        _ => None,
+    }
+}
+
+/// Like `natural`, for numbers that fit in a `usize`, which is only 32 bits on wasm32.
+pub fn number(expression: &Expression, limits: &Limits) -> Option<usize> {
+    natural(expression, limits)?
+        .to_u64()
+        .and_then(|n| usize::try_from(n).ok())
+}
+
+pub fn boolean(expression: &Expression, limits: &Limits) -> Option<bool> {
+    match evaluate(binders(expression, 2, limits)?, limits)? {
+        Expression::Variable { index: Some(1) } => Some(true),
+        Expression::Variable { index: Some(0) } => Some(false),
+        _ => None,
+    }
+}
+
+pub fn character(expression: &Expression, limits: &Limits) -> Option<char> {
+    number(expression, limits)
+        .and_then(|n| u32::try_from(n).ok())
+        .and_then(::std::char::from_u32)
+}
+
+/// Returns the elements of a right-fold encoded list, each lifted out of the list's binders.
+pub fn list(expression: &Expression, limits: &Limits) -> Option<Vec<Expression>> {
+    let mut body = binders(expression, 2, limits)?;
+    let mut elements = Vec::new();
+
+    loop {
+        let mut evaluated = evaluate(body, limits)?;
+        if is_variable(&evaluated, 0) {
+            return Some(elements);
+        }
+
+        let (element, rest) = arguments(&mut evaluated, 1)?;
+        elements.push(lowered(&element, 2)?);
+        body = rest;
+    }
+}
+
+/// Returns the elements of a Scott encoded list.
+pub fn scott_list(expression: &Expression, limits: &Limits) -> Option<Vec<Expression>> {
+    let mut list = expression.clone();
+    let mut elements = Vec::new();
+
+    loop {
+        let mut evaluated = evaluate(binders(&list, 2, limits)?, limits)?;
+        if is_variable(&evaluated, 1) {
+            return Some(elements);
+        }
+
+        let (head, tail) = arguments(&mut evaluated, 0)?;
+        elements.push(lowered(&head, 2)?);
+        list = lowered(&tail, 2)?;
+    }
+}
+
+/// Decodes a list of characters, trying a right fold before a Scott encoded list.
+///
+/// The two cannot be confused: the empty right fold returns its second binder and the empty
+/// Scott list its first one.
+pub fn string(expression: &Expression, limits: &Limits) -> Option<String> {
+    list(expression, limits)
+        .or_else(|| scott_list(expression, limits))?
+        .iter()
+        .map(|element| character(element, limits))
+        .collect()
+}
+
+pub fn pair(expression: &Expression, limits: &Limits) -> Option<(Expression, Expression)> {
+    let mut evaluated = evaluate(binders(expression, 1, limits)?, limits)?;
+    let (first, second) = arguments(&mut evaluated, 0)?;
+    Some((lowered(&first, 1)?, lowered(&second, 1)?))
+}
+
+/// Evaluates `expression` within `limits`, or returns `None` if they stop it.
+fn evaluate(expression: Expression, limits: &Limits) -> Option<Expression> {
+    match expression.evaluate_with_limits(limits) {
+        Outcome::Normal(expression) => Some(expression),
+        _ => None,
+    }
+}
+
+/// Evaluates `expression` and strips `count` leading abstractions from it.
+fn binders(expression: &Expression, count: usize, limits: &Limits) -> Option<Expression> {
+    let mut expression = expression.clone();
+    for _ in 0..count {
+        let mut evaluated = evaluate(expression, limits)?;
+        expression = match &mut evaluated {
+            Expression::Abstraction { expression, .. } => expression.take(),
+            _ => return None,
+        };
+    }
+    Some(expression)
+}
+
+fn is_variable(expression: &Expression, index: usize) -> bool {
+    match expression {
+        Expression::Variable { index: Some(i) } => *i == index,
+        _ => false,
+    }
+}
+
+/// Takes `a` and `b` out of `expression` if it is `v a b` for the variable `v` with `index`.
+fn arguments(expression: &mut Expression, index: usize) -> Option<(Expression, Expression)> {
+    match expression {
+        Expression::Application {
+            applicand,
+            argument: second,
+        } => match &mut **applicand {
+            Expression::Application {
+                applicand,
+                argument: first,
+            } if is_variable(applicand, index) => Some((first.take(), second.take())),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Moves `expression` out from under `count` binders, failing if it refers to any of them.
+fn lowered(expression: &Expression, count: usize) -> Option<Expression> {
+    let mut lowered = expression.clone();
+    let mut captured = false;
+
+    lowered.for_each_variable_mut(|variable, depth| {
+        if let Expression::Variable { index: Some(index) } = variable {
+            if *index >= depth + count {
+                *index -= count;
+            } else if *index >= depth {
+                captured = true;
+            }
+        }
+    });
+
+    if captured {
+        None
+    } else {
+        Some(lowered)
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Value::Number(n) => n.fmt(f),
+            Value::Boolean(b) => b.fmt(f),
+            Value::Character(c) => write!(f, "{:?}", c),
+            Value::List(elements) => {
+                f.write_str("[")?;
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    element.fmt(f)?;
+                }
+                f.write_str("]")
+            }
+            Value::Pair(first, second) => write!(f, "({}, {})", first, second),
+            Value::String(s) => write!(f, "{:?}", s),
+        }
+    }
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Type::Number => f.write_str("number"),
+            Type::Boolean => f.write_str("boolean"),
+            Type::Character => f.write_str("character"),
+            Type::List(ty) => write!(f, "list({})", ty),
+            Type::ScottList(ty) => write!(f, "scott_list({})", ty),
+            Type::Pair(first, second) => write!(f, "pair({}, {})", first, second),
+            Type::String => f.write_str("string"),
+        }
+    }
+}
+
+/// Parses type names such as `number` or `pair(list(character), boolean)`.
+impl FromStr for Type {
+    type Err = InvalidType;
+
+    fn from_str(s: &str) -> Result<Type, InvalidType> {
+        let invalid = || InvalidType(s.to_owned());
+        let s = s.trim();
+
+        let (name, arguments) = match s.find('(') {
+            Some(open) if s.ends_with(')') => {
+                (&s[..open], split_arguments(&s[open + 1..s.len() - 1]))
+            }
+            _ => (s, Vec::new()),
+        };
+        let arguments = arguments
+            .into_iter()
+            .map(str::parse)
+            .collect::<Result<Vec<Type>, _>>()
+            .map_err(|_| invalid())?;
+
+        match (name.trim(), arguments.as_slice()) {
+            ("number", []) => Ok(Type::Number),
+            ("boolean", []) => Ok(Type::Boolean),
+            ("character", []) => Ok(Type::Character),
+            ("string", []) => Ok(Type::String),
+            ("list", [ty]) => Ok(Type::List(box ty.clone())),
+            ("scott_list", [ty]) => Ok(Type::ScottList(box ty.clone())),
+            ("pair", [first, second]) => Ok(Type::Pair(box first.clone(), box second.clone())),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// Splits at the commas that are not nested inside parentheses.
+fn split_arguments(s: &str) -> Vec<&str> {
+    let mut arguments = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                arguments.push(&s[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    arguments.push(&s[start..]);
+    arguments
+}
+
+impl Display for InvalidType {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "Invalid type \"{}\"", self.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ast::{ListEncoding, Options};
+    use lexer::Lexer;
+    use parser::parse;
+
+    fn translate(source: &str) -> Expression {
+        let program = parse(&mut Lexer::new(source)).unwrap();
+        Expression::from_cst_program_with(
+            &program,
+            &Options {
+                open_terms: true,
+                ..Options::default()
+            },
+        ).unwrap()
+    }
+
+    #[test]
+    fn test_decode_number() {
+        let limits = Limits::default();
+        assert_eq!(Some(0), number(&translate("0;"), &limits));
+        assert_eq!(Some(3), number(&translate("3;"), &limits));
+        assert_eq!(Some(2), number(&translate(r"(\n f x. f (n f x)) 1;"), &limits));
+        assert_eq!(None, number(&translate(r"\f x. f;"), &limits));
+
+        // Integers that do not fit are not decoded as numbers, instead of wrapping around.
+        let big = Expression::Integer {
+            value: "18446744073709551616".parse().unwrap(),
+        };
+        assert_eq!(None, number(&big, &limits));
+        assert_eq!("18446744073709551616", natural(&big, &limits).unwrap().to_string());
+        assert_eq!(Some(Natural::from(3)), natural(&translate("3;"), &limits));
+    }
+
+    #[test]
+    fn test_decode_boolean_and_character() {
+        let limits = Limits::default();
+        assert_eq!(Some(true), boolean(&translate(r"\x y. x;"), &limits));
+        assert_eq!(Some(false), boolean(&translate(r"\x y. y;"), &limits));
+        assert_eq!(None, boolean(&translate(r"\x. x;"), &limits));
+        assert_eq!(Some('a'), character(&translate("'a';"), &limits));
+        assert_eq!(Some('😀'), character(&translate(r"'\u{1F600}';"), &limits));
+    }
+
+    #[test]
+    fn test_decode_list_and_pair() {
+        let limits = Limits::default();
+        let church = translate(r"let cons = \h t c n. c h (t c n); cons 1 (cons 2 0);");
+        assert_eq!(
+            Some(Value::List(vec![
+                Value::Number(Natural::from(1)),
+                Value::Number(Natural::from(2)),
+            ])),
+            decode(&church, &Type::List(box Type::Number), &limits)
+        );
+
+        let scott = translate(r"let cons = \h t n c. c h t; let nil = \n c. n; cons 'a' nil;");
+        assert_eq!(
+            Some(Value::List(vec![Value::Character('a')])),
+            decode(&scott, &Type::ScottList(box Type::Character), &limits)
+        );
+
+        let pair = translate(r"(\a b p. p a b) 1 (\x y. x);");
+        assert_eq!(
+            "(1, true)",
+            decode(&pair, &"pair(number, boolean)".parse().unwrap(), &limits)
+                .unwrap()
+                .to_string()
+        );
+        assert_eq!(None, list(&translate(r"\c n. c c n;"), &limits));
+    }
+
+    #[test]
+    fn test_decode_string() {
+        let limits = Limits::default();
+        let program = parse(&mut Lexer::new(r#"(\s. s) "a\"é\n";"#)).unwrap();
+        for &lists in &[ListEncoding::Church, ListEncoding::Scott] {
+            let options = Options {
+                lists,
+                ..Options::default()
+            };
+            let expression = Expression::from_cst_program_with(&program, &options).unwrap();
+            assert_eq!(Some("a\"é\n".to_owned()), string(&expression, &limits));
+        }
+
+        assert_eq!(Some(String::new()), string(&translate(r#""";"#), &limits));
+        assert_eq!(Some(String::new()), string(&translate(r"\n c. n;"), &limits));
+        assert_eq!(
+            r#""ab""#,
+            decode(&translate(r#""ab";"#), &Type::String, &limits)
+                .unwrap()
+                .to_string()
+        );
+        assert_eq!(None, string(&translate(r"\c n. c (\x. x) n;"), &limits));
+    }
+
+    #[test]
+    fn test_decode_within_limits() {
+        let limits = Limits {
+            steps: Some(1000),
+            ..Limits::default()
+        };
+        // Weak head normal forms whose bodies have no normal form.
+        let divergent = translate(r"\f x. (\y. y y) (\y. y y);");
+        assert_eq!(None, number(&divergent, &limits));
+        assert_eq!(None, boolean(&divergent, &limits));
+        assert_eq!(None, decode(&divergent, &Type::List(box Type::Number), &limits));
+        assert_eq!(Some(3), number(&translate("3;"), &limits));
+    }
+
+    #[test]
+    fn test_parse_type() {
+        assert_eq!(
+            Ok(Type::Pair(
+                box Type::List(box Type::Character),
+                box Type::Boolean
+            )),
+            "pair(list(character), boolean)".parse()
+        );
+        assert_eq!(
+            Err(InvalidType("list".to_owned())),
+            "list".parse::<Type>()
+        );
+    }
+}
@@ -0,0 +1,99 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use token::Span;
+
+/// A message pointing into the source, rendered with the offending line and a caret underline.
+///
+/// ```text
+/// error: Expected ';', found ')'
+///  --> 1:10
+///   |
+/// 1 | let x = y)
+///   |          ^
+/// ```
+pub struct Diagnostic<'a, T> {
+    pub source: &'a str,
+    pub span: Span,
+    pub message: T,
+}
+
+impl<'a, T> Diagnostic<'a, T>
+where
+    T: Display,
+{
+    pub fn new(source: &'a str, span: Span, message: T) -> Diagnostic<'a, T> {
+        Diagnostic {
+            source,
+            span,
+            message,
+        }
+    }
+}
+
+impl<'a, T> Display for Diagnostic<'a, T>
+where
+    T: Display,
+{
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let Span { start, end } = self.span;
+        let line = self.source.lines().nth(start.line).unwrap_or("");
+        let number = (start.line + 1).to_string();
+        let gutter = " ".repeat(number.len());
+
+        let width = if end.line == start.line && end.column > start.column {
+            end.column - start.column
+        } else {
+            1
+        };
+
+        writeln!(f, "error: {}", self.message)?;
+        writeln!(f, "{}--> {}", gutter, start)?;
+        writeln!(f, "{} |", gutter)?;
+        writeln!(f, "{} | {}", number, line)?;
+        write!(
+            f,
+            "{} | {}{}",
+            gutter,
+            " ".repeat(start.column),
+            "^".repeat(width)
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use token::Position;
+
+    #[test]
+    fn test_diagnostic_display() {
+        let diagnostic = Diagnostic::new(
+            "let x = y;\nlet z = (x;",
+            Span::new(Position::new(21, 1, 10), Position::new(22, 1, 11)),
+            "Expected ')', found ';'",
+        );
+        let expected = [
+            "error: Expected ')', found ';'",
+            " --> 2:11",
+            "  |",
+            "2 | let z = (x;",
+            "  |           ^",
+        ].join("\n");
+        assert_eq!(expected, format!("{}", diagnostic));
+
+        // Columns count characters, so the caret lines up after multibyte names.
+        let diagnostic = Diagnostic::new(
+            "λαβ. αβ)",
+            Span::new(Position::new(12, 0, 7), Position::new(13, 0, 8)),
+            "Expected ';', found ')'",
+        );
+        let expected = [
+            "error: Expected ';', found ')'",
+            " --> 1:8",
+            "  |",
+            "1 | λαβ. αβ)",
+            "  |        ^",
+        ].join("\n");
+        assert_eq!(expected, format!("{}", diagnostic));
+    }
+}
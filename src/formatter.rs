@@ -0,0 +1,499 @@
+use cst::{
+    AbstractionExpression, ApplicationExpression, Binding, Expression, Identifier, LetExpression,
+    Program, Statement,
+};
+use token::{Token, TokenKind};
+
+/// Lines are broken once they would be longer than this.
+pub const WIDTH: usize = 100;
+const INDENT: usize = 4;
+
+/// Prints a program in the canonical layout.
+///
+/// An expression stays on one line if it fits and was written on one line. Otherwise abstractions
+/// break after the dot, applications keep the first argument next to the function and put each
+/// of the others on a line of its own, one level deeper, and `let` expressions put each binding
+/// and the `in` on a line of its own. Each further binding of a `let rec` group starts a line
+/// with `and`. Comments and blank lines between statements are only kept
+/// if the program was parsed from `Lexer::lossless`.
+pub fn format(program: &Program) -> String {
+    let Program(statements) = program;
+    let mut formatter = Formatter::new(false);
+    for statement in statements {
+        formatter.statement(statement);
+    }
+
+    let mut output = formatter.output.trim_end().to_owned();
+    if !output.is_empty() {
+        output.push('\n');
+    }
+    output
+}
+
+struct Formatter {
+    output: String,
+    /// A line break waiting for the next text: the indentation of the new line, and whether a
+    /// blank line goes before it.
+    newline: Option<(usize, bool)>,
+    /// Lays everything out on one line, failing on anything that needs more.
+    flat: bool,
+    failed: bool,
+}
+
+impl Formatter {
+    fn new(flat: bool) -> Formatter {
+        Formatter {
+            output: String::new(),
+            newline: Some((0, false)),
+            flat,
+            failed: false,
+        }
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        let tokens = statement.tokens();
+        match statement {
+            Statement::Expression(expression_statement) => {
+                self.expression(&expression_statement.expression, 0)
+            }
+
+            Statement::Let(let_statement) => {
+                self.token(find(tokens, &TokenKind::Let), "let", 0);
+                self.space();
+                self.identifier(&let_statement.variable, 0);
+                self.space();
+                self.token(find(tokens, &TokenKind::Equal), "=", 0);
+                self.space();
+                self.expression(&let_statement.expression, 0);
+            }
+
+            Statement::LetRec(let_rec_statement) => {
+                self.token(find(tokens, &TokenKind::Let), "let", 0);
+                self.space();
+                self.token(find(tokens, &TokenKind::Rec), "rec", 0);
+                let mut ands = tokens
+                    .iter()
+                    .filter(|token| token.kind == Some(TokenKind::And));
+                for (i, binding) in let_rec_statement.bindings.iter().enumerate() {
+                    if i > 0 {
+                        self.newline(0);
+                        self.token(ands.next(), "and", 0);
+                    }
+                    self.space();
+                    self.binding(binding, 0);
+                }
+            }
+
+            // Only the tokens skipped by a lossless parse are left to print.
+            Statement::Error(..) => {
+                for (i, token) in tokens.iter().filter(|token| token.kind.is_some()).enumerate() {
+                    if i > 0 {
+                        self.space();
+                    }
+                    self.token(Some(token), "", 0);
+                }
+            }
+        }
+
+        if let Statement::Expression(..) | Statement::Let(..) | Statement::LetRec(..) = statement {
+            self.token(find(tokens, &TokenKind::Semicolon), ";", 0);
+        }
+        self.newline(0);
+
+        // The end of input, with the comments after the last statement.
+        if let Some(token) = tokens.iter().find(|token| token.kind.is_none()) {
+            self.token(Some(token), "", 0);
+        }
+    }
+
+    fn expression(&mut self, expression: &Expression, indent: usize) {
+        let span = expression.span();
+        if self.flat && span.start.line != span.end.line {
+            self.failed = true;
+            return;
+        }
+
+        if !self.flat {
+            if let Some(text) = flatten(expression) {
+                if self.column() + text.chars().count() <= WIDTH {
+                    if let Some(token) = first_token(expression) {
+                        self.trivia(token, indent);
+                    }
+                    self.write(&text);
+                    return;
+                }
+            }
+        }
+
+        match expression {
+            Expression::Variable(variable) => self.identifier(&variable.identifier, indent),
+            Expression::Abstraction(abstraction) => self.abstraction(abstraction, indent),
+            Expression::Application(application) => self.application(application, indent),
+            Expression::Let(let_expression) => self.let_expression(let_expression, indent),
+            Expression::Number(number) => self.token(number.tokens.first(), &number.value, indent),
+            Expression::Character(character) => {
+                let text = format!("{:?}", character.value);
+                self.token(character.tokens.first(), &text, indent)
+            }
+            Expression::String(string) => {
+                let text = format!("{:?}", string.value);
+                self.token(string.tokens.first(), &text, indent)
+            }
+        }
+    }
+
+    fn abstraction(&mut self, abstraction: &AbstractionExpression, indent: usize) {
+        self.token(find(&abstraction.tokens, &TokenKind::Lambda), "\\", indent);
+        for (i, parameter) in abstraction.parameters.iter().enumerate() {
+            if i > 0 {
+                self.space();
+            }
+            self.identifier(parameter, indent);
+        }
+        self.token(find(&abstraction.tokens, &TokenKind::Dot), ".", indent);
+
+        if self.flat {
+            self.space();
+            self.expression(&abstraction.expression, indent);
+        } else {
+            self.newline(indent + INDENT);
+            self.expression(&abstraction.expression, indent + INDENT);
+        }
+    }
+
+    fn application(&mut self, application: &ApplicationExpression, indent: usize) {
+        let elements = elements(application);
+        let count = elements.len();
+        for (i, (brackets, expression)) in elements.into_iter().enumerate() {
+            let indent = if i == 0 || self.flat || (i == 1 && count > 2) {
+                if i > 0 {
+                    self.space();
+                }
+                indent
+            } else {
+                self.newline(indent + INDENT);
+                indent + INDENT
+            };
+
+            let bracketed = brackets.is_some() || match expression {
+                Expression::Application(..) => true,
+                Expression::Abstraction(..) | Expression::Let(..) => count > 1,
+                _ => false,
+            };
+            if bracketed {
+                self.token(brackets.map(|(open, _)| open), "(", indent);
+                self.expression(expression, indent);
+                self.token(brackets.map(|(_, close)| close), ")", indent);
+            } else {
+                self.expression(expression, indent);
+            }
+        }
+    }
+
+    fn let_expression(&mut self, let_expression: &LetExpression, indent: usize) {
+        let tokens = &let_expression.tokens;
+        let separator = if let_expression.recursive {
+            TokenKind::And
+        } else {
+            TokenKind::Comma
+        };
+        let mut separators = tokens.iter().filter(|token| token.kind.as_ref() == Some(&separator));
+
+        self.token(find(tokens, &TokenKind::Let), "let", indent);
+        self.space();
+        if let_expression.recursive {
+            self.token(find(tokens, &TokenKind::Rec), "rec", indent);
+            self.space();
+        }
+        for (i, binding) in let_expression.bindings.iter().enumerate() {
+            if i > 0 && let_expression.recursive {
+                if self.flat {
+                    self.space();
+                } else {
+                    self.newline(indent + INDENT);
+                }
+                self.token(separators.next(), "and", indent + INDENT);
+                self.space();
+            } else if i > 0 {
+                self.token(separators.next(), ",", indent);
+                if self.flat {
+                    self.space();
+                } else {
+                    self.newline(indent + INDENT);
+                }
+            }
+            self.binding(binding, indent + INDENT);
+        }
+
+        if self.flat {
+            self.space();
+        } else {
+            self.newline(indent);
+        }
+        self.token(find(tokens, &TokenKind::In), "in", indent);
+        self.space();
+        self.expression(&let_expression.expression, indent);
+    }
+
+    fn binding(&mut self, binding: &Binding, indent: usize) {
+        self.identifier(&binding.variable, indent);
+        self.space();
+        self.token(find(&binding.tokens, &TokenKind::Equal), "=", indent);
+        self.space();
+        self.expression(&binding.expression, indent);
+    }
+
+    fn identifier(&mut self, identifier: &Identifier, indent: usize) {
+        self.token(identifier.tokens.first(), &identifier.name, indent);
+    }
+
+    /// Writes a token after the comments before it, or `text` if the program kept no tokens.
+    fn token(&mut self, token: Option<&Token>, text: &str, indent: usize) {
+        let token = match token {
+            Some(token) => token,
+            None => return self.write(text),
+        };
+
+        // Comments before the first token are written by whoever asked for the flat layout.
+        if !self.flat {
+            self.trivia(token, indent);
+        } else if !self.output.is_empty() && token.trivia.iter().any(is_comment) {
+            self.failed = true;
+        }
+        self.write(token.text.as_ref().map_or(text, String::as_str));
+    }
+
+    /// Writes the comments before a token, keeping one blank line between statements.
+    fn trivia(&mut self, token: &Token, indent: usize) {
+        let mut newlines = 0;
+        for (i, trivia) in token.trivia.iter().enumerate() {
+            let text = trivia.text.as_ref().map_or("", String::as_str);
+            if is_comment(trivia) {
+                let line_break = text.starts_with("//") || token
+                    .trivia
+                    .get(i + 1)
+                    .and_then(|next| next.text.as_ref())
+                    .map_or(false, |next| next.contains('\n'));
+                self.comment(text, newlines, line_break, indent);
+                newlines = 0;
+            } else {
+                newlines += text.matches('\n').count();
+            }
+        }
+
+        if newlines > 1 && indent == 0 {
+            self.blank_line();
+        }
+    }
+
+    fn comment(&mut self, text: &str, newlines: usize, line_break: bool, indent: usize) {
+        let line_start = self.newline.is_some();
+        if line_start && newlines == 0 && !self.output.is_empty() {
+            // A comment at the end of a line stays there.
+            self.trim();
+            self.output.push(' ');
+            self.output.push_str(text);
+            return;
+        }
+
+        let indent = if line_start { indent } else { indent + INDENT };
+        if newlines > 0 {
+            self.newline(indent);
+            if newlines > 1 && indent == 0 {
+                self.blank_line();
+            }
+        } else if !line_start {
+            self.trim();
+            self.output.push(' ');
+        }
+
+        self.write(text);
+        if line_break {
+            self.newline(indent);
+        } else {
+            self.output.push(' ');
+        }
+    }
+
+    fn write(&mut self, text: &str) {
+        if let Some((indent, blank)) = self.newline.take() {
+            self.trim();
+            if !self.output.is_empty() {
+                self.output.push('\n');
+                if blank {
+                    self.output.push('\n');
+                }
+                self.output.push_str(&" ".repeat(indent));
+            }
+        }
+        self.output.push_str(text);
+    }
+
+    fn space(&mut self) {
+        if self.newline.is_none() {
+            self.output.push(' ');
+        }
+    }
+
+    fn newline(&mut self, indent: usize) {
+        if self.flat {
+            self.failed = true;
+        }
+        let blank = self.newline.map_or(false, |(_, blank)| blank);
+        self.newline = Some((indent, blank));
+    }
+
+    fn blank_line(&mut self) {
+        if let Some((_, ref mut blank)) = self.newline {
+            *blank = true;
+        }
+    }
+
+    fn trim(&mut self) {
+        let len = self.output.trim_end_matches(' ').len();
+        self.output.truncate(len);
+    }
+
+    fn column(&self) -> usize {
+        match self.newline {
+            Some((indent, _)) => indent,
+            None => {
+                let start = self.output.rfind('\n').map_or(0, |i| i + 1);
+                self.output[start..].chars().count()
+            }
+        }
+    }
+}
+
+/// Lays out an expression on one line, unless it was written on several or has comments inside.
+fn flatten(expression: &Expression) -> Option<String> {
+    let mut formatter = Formatter::new(true);
+    formatter.expression(expression, 0);
+    if formatter.failed || formatter.output.contains('\n') {
+        None
+    } else {
+        Some(formatter.output)
+    }
+}
+
+/// Pairs the elements of an application with the brackets written around them.
+fn elements(application: &ApplicationExpression) -> Vec<(Option<(&Token, &Token)>, &Expression)> {
+    let tokens = &application.tokens;
+    let mut next = 0;
+    application
+        .expressions
+        .iter()
+        .map(|expression| {
+            let brackets = if next + 1 < tokens.len()
+                && tokens[next].span.start.offset < expression.span().start.offset
+            {
+                next += 2;
+                Some((&tokens[next - 2], &tokens[next - 1]))
+            } else {
+                None
+            };
+            (brackets, expression)
+        })
+        .collect()
+}
+
+fn first_token(expression: &Expression) -> Option<&Token> {
+    match expression {
+        Expression::Variable(variable) => variable.identifier.tokens.first(),
+        Expression::Abstraction(abstraction) => abstraction.tokens.first(),
+        Expression::Application(application) => {
+            let inner = application.expressions.first().and_then(first_token);
+            match (application.tokens.first(), inner) {
+                (Some(bracket), Some(inner))
+                    if bracket.span.start.offset < inner.span.start.offset =>
+                {
+                    Some(bracket)
+                }
+                (bracket, inner) => inner.or(bracket),
+            }
+        }
+        Expression::Let(let_expression) => let_expression.tokens.first(),
+        Expression::Number(number) => number.tokens.first(),
+        Expression::Character(character) => character.tokens.first(),
+        Expression::String(string) => string.tokens.first(),
+    }
+}
+
+fn find<'a>(tokens: &'a [Token], kind: &TokenKind) -> Option<&'a Token> {
+    tokens.iter().find(|token| token.kind.as_ref() == Some(kind))
+}
+
+fn is_comment(token: &Token) -> bool {
+    token.kind == Some(TokenKind::Comment)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use lexer::Lexer;
+    use parser::parse;
+
+    fn format_source(source: &str) -> String {
+        format(&parse(&mut Lexer::lossless(source)).unwrap())
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!("let id = \\x. x;\nid (\\y. y);\n", format_source("let id=\\x.x;id(\\y.y);"));
+        assert_eq!("f (\\x. (x));\n", format_source("f \\x . (x) ;\n"));
+
+        // Without tokens the brackets are worked out from the tree.
+        let program = parse(&mut Lexer::new("f (\\x. x) (g y) z;")).unwrap();
+        assert_eq!("f (\\x. x) (g y) z;\n", format(&program));
+    }
+
+    #[test]
+    fn test_line_breaks() {
+        let source = "let f = \\x.\n  g x (h x) x;\nf;\n";
+        assert_eq!("let f = \\x.\n    g x (h x) x;\nf;\n", format_source(source));
+
+        let source = "let f = \\x. g\n  x (h x)\n x;\nf;\n";
+        let expected = "let f = \\x.\n    g x\n        (h x)\n        x;\nf;\n";
+        assert_eq!(expected, format_source(source));
+
+        let long = "a".repeat(40);
+        let source = format!("f {0} {0} {0};", long);
+        let expected = format!("f {0}\n    {0}\n    {0};\n", long);
+        assert_eq!(expected, format_source(&source));
+        assert_eq!(expected, format_source(&expected));
+    }
+
+    #[test]
+    fn test_let_expression() {
+        let source = "let f = \\x.\n let y = g x,\n z = h y in\n k z;\nf (let a = b in a) c;\n";
+        let expected =
+            "let f = \\x.\n    let y = g x,\n        z = h y\n    in k z;\nf (let a = b in a) c;\n";
+        assert_eq!(expected, format_source(source));
+
+        let program = parse(&mut Lexer::new("f let a = b, c = d in a;")).unwrap();
+        assert_eq!("f (let a = b, c = d in a);\n", format(&program));
+    }
+
+    #[test]
+    fn test_let_rec() {
+        let source = "let rec f = \\x. g x  and g=\\x. f x;f (let rec h = h in h);\n";
+        let expected = "let rec f = \\x. g x\nand g = \\x. f x;\nf (let rec h = h in h);\n";
+        assert_eq!(expected, format_source(source));
+
+        let source = "let rec a = b\n and b = a in a;\n";
+        assert_eq!("let rec a = b\n    and b = a\nin a;\n", format_source(source));
+
+        let program = parse(&mut Lexer::new("let rec a = b and b = a; a;")).unwrap();
+        assert_eq!("let rec a = b\nand b = a;\na;\n", format(&program));
+    }
+
+    #[test]
+    fn test_comments() {
+        let source =
+            "/// Doc\nlet x = y; // end\n\n\n/* a */ x /* b */ 'c'\n    // c\n    \"d\";\n";
+        let expected =
+            "/// Doc\nlet x = y; // end\n\n/* a */ x /* b */ 'c'\n    // c\n    \"d\";\n";
+        assert_eq!(expected, format_source(source));
+        assert_eq!(expected, format_source(expected));
+    }
+}
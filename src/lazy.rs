@@ -0,0 +1,583 @@
+use ast::{EvalStats, Expression, Limits, Name, Outcome};
+use natural::Natural;
+use primitive;
+use primitive::Primitive;
+use std::cell::{Cell, RefCell};
+use std::cmp;
+use std::iter;
+use std::mem;
+use std::rc::Rc;
+
+/// Reduces `expression` to weak head normal form by call-by-need.
+///
+/// Arguments become shared thunks that are evaluated at most once and updated in place. The
+/// result is the one call-by-name gives, except that arguments that were evaluated appear as
+/// their values.
+pub fn evaluate(expression: &Expression) -> Expression {
+    evaluate_with_limits(expression, &Limits::default()).into_expression()
+}
+
+/// Like `evaluate`, but gives up once any of `limits` is exceeded.
+pub fn evaluate_with_limits(expression: &Expression, limits: &Limits) -> Outcome {
+    evaluate_counting(expression, limits, &mut EvalStats::default())
+}
+
+/// Like `evaluate_with_limits`, recording what the machine did in `stats`.
+pub(crate) fn evaluate_counting(
+    expression: &Expression,
+    limits: &Limits,
+    stats: &mut EvalStats,
+) -> Outcome {
+    // What comparing primitives return, as closures over these.
+    let booleans = [
+        Expression::from(primitive::Value::Boolean(false)),
+        Expression::from(primitive::Value::Boolean(true)),
+    ];
+
+    let mut stack = Vec::new();
+    let live = Rc::new(Cell::new(0));
+    let mut expression = expression;
+    let mut environment = Environment::default();
+    let mut steps = 0;
+
+    loop {
+        let mut value = match expression {
+            Expression::Application {
+                applicand,
+                argument,
+            } => {
+                // Passing a variable on shares its thunk instead of wrapping it in another one.
+                let thunk = match **argument {
+                    Expression::Variable { index: Some(index) } => match environment.get(index) {
+                        Some(Binding::Thunk(thunk)) => Some(thunk.clone()),
+                        _ => None,
+                    },
+                    _ => None,
+                }.unwrap_or_else(|| Thunk::new(argument, environment.clone(), &live));
+
+                stack.push(Frame::Argument(thunk));
+                // What the machine holds stands in for the size of the term.
+                let size = live.get() + stack.len();
+                stats.peak_size = cmp::max(stats.peak_size, size);
+                if limits.size.map_or(false, |max| size > max) {
+                    let expression =
+                        read_back(ReadBack::Expression(expression, environment.clone(), 0));
+                    return Outcome::SizeLimit(applied(expression, &stack[..stack.len() - 1]));
+                }
+
+                expression = applicand;
+                continue;
+            }
+
+            Expression::Abstraction { name, expression } => {
+                Value::Closure(name, expression, environment.clone())
+            }
+
+            Expression::Constant { name } => Value::Neutral(Head::Constant(name), Vec::new()),
+
+            Expression::Integer { value } => Value::Integer(value.clone()),
+
+            Expression::Primitive { primitive } => Value::Primitive(*primitive, Vec::new()),
+
+            Expression::Variable { index: None } => Value::Neutral(Head::Free(None), Vec::new()),
+
+            Expression::Variable { index: Some(index) } => match environment.get(*index) {
+                Some(Binding::Thunk(thunk)) => {
+                    stats.substitutions += 1;
+                    match force(thunk, &mut stack) {
+                        Some(value) => value,
+                        None => {
+                            expression = thunk.expression;
+                            environment = thunk.environment.clone();
+                            continue;
+                        }
+                    }
+                }
+                Some(Binding::Bound(..)) => unreachable!(),
+                None => Value::Neutral(Head::Free(Some(index - environment.len())), Vec::new()),
+            },
+        };
+
+        loop {
+            match stack.pop() {
+                None => return Outcome::Normal(read_back(ReadBack::Value(value, 0))),
+
+                Some(Frame::Update(thunk)) => *thunk.value.borrow_mut() = Some(value.clone()),
+
+                Some(Frame::Argument(thunk)) => match value {
+                    Value::Closure(name, body, closure) => {
+                        let stop: Option<fn(Expression) -> Outcome> =
+                            if limits.steps.map_or(false, |max| steps >= max) {
+                                Some(Outcome::StepLimit)
+                            } else if limits.is_cancelled() {
+                                Some(Outcome::Cancelled)
+                            } else {
+                                None
+                            };
+                        if let Some(stop) = stop {
+                            stack.push(Frame::Argument(thunk));
+                            let closure = Value::Closure(name, body, closure);
+                            let expression = read_back(ReadBack::Value(closure, 0));
+                            return stop(applied(expression, &stack));
+                        }
+
+                        steps += 1;
+                        stats.reduction(name);
+                        expression = body;
+                        environment = closure.pushed(Binding::Thunk(thunk));
+                        break;
+                    }
+                    Value::Neutral(head, mut arguments) => {
+                        arguments.push(thunk);
+                        value = Value::Neutral(head, arguments);
+                    }
+                    Value::Integer(n) => value = Value::Neutral(Head::Integer(n), vec![thunk]),
+                    Value::Primitive(primitive, mut arguments) => {
+                        arguments.push(thunk);
+                        if arguments.len() < primitive.arity() {
+                            value = Value::Primitive(primitive, arguments);
+                            continue;
+                        }
+
+                        let first = arguments[0].clone();
+                        stack.push(Frame::Strict(primitive, arguments, Vec::new()));
+                        match force(&first, &mut stack) {
+                            Some(forced) => value = forced,
+                            None => {
+                                expression = first.expression;
+                                environment = first.environment.clone();
+                                break;
+                            }
+                        }
+                    }
+                },
+
+                Some(Frame::Strict(primitive, arguments, mut integers)) => {
+                    match value {
+                        Value::Integer(n) => integers.push(n),
+                        _ => {
+                            value = Value::Neutral(Head::Primitive(primitive), arguments);
+                            continue;
+                        }
+                    }
+
+                    if integers.len() == arguments.len() {
+                        value = match primitive.apply(&integers[0], &integers[1]) {
+                            Some(primitive::Value::Integer(n)) => Value::Integer(n),
+                            Some(primitive::Value::Boolean(b)) => match &booleans[b as usize] {
+                                Expression::Abstraction { name, expression } => {
+                                    Value::Closure(name, expression, Environment::default())
+                                }
+                                _ => unreachable!(),
+                            },
+                            None => {
+                                value = Value::Neutral(Head::Primitive(primitive), arguments);
+                                continue;
+                            }
+                        };
+                        stats.delta_reductions += 1;
+                        continue;
+                    }
+
+                    let next = arguments[integers.len()].clone();
+                    stack.push(Frame::Strict(primitive, arguments, integers));
+                    match force(&next, &mut stack) {
+                        Some(forced) => value = forced,
+                        None => {
+                            expression = next.expression;
+                            environment = next.environment.clone();
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Returns the value of `thunk` if it has been evaluated, and otherwise pushes the frame that
+/// will record it.
+fn force<'a>(thunk: &Rc<Thunk<'a>>, stack: &mut Vec<Frame<'a>>) -> Option<Value<'a>> {
+    let value = thunk.value.borrow().clone();
+    if value.is_none() {
+        stack.push(Frame::Update(thunk.clone()));
+    }
+    value
+}
+
+/// Applies `expression` to the pending arguments on `stack`, giving the term the machine is in
+/// the middle of reducing.
+fn applied(expression: Expression, stack: &[Frame]) -> Expression {
+    stack
+        .iter()
+        .rev()
+        .fold(expression, |applicand, frame| match frame {
+            Frame::Argument(thunk) => Expression::Application {
+                applicand: box applicand,
+                argument: box read_back(ReadBack::Thunk(thunk.clone(), 0)),
+            },
+            Frame::Update(..) => applicand,
+            Frame::Strict(primitive, arguments, integers) => integers
+                .iter()
+                .map(|value| Expression::Integer {
+                    value: value.clone(),
+                })
+                .chain(iter::once(applicand))
+                .chain(
+                    arguments[integers.len() + 1..]
+                        .iter()
+                        .map(|thunk| read_back(ReadBack::Thunk(thunk.clone(), 0))),
+                ).fold(
+                    Expression::Primitive {
+                        primitive: *primitive,
+                    },
+                    |applicand, argument| Expression::Application {
+                        applicand: box applicand,
+                        argument: box argument,
+                    },
+                ),
+        })
+}
+
+struct Thunk<'a> {
+    expression: &'a Expression,
+    environment: Environment<'a>,
+    value: RefCell<Option<Value<'a>>>,
+    /// How many thunks of the evaluation are alive.
+    live: Rc<Cell<usize>>,
+}
+
+#[derive(Clone)]
+enum Value<'a> {
+    Closure(&'a Name, &'a Expression, Environment<'a>),
+    Neutral(Head<'a>, Vec<Rc<Thunk<'a>>>),
+    Integer(Natural),
+    /// A primitive that still takes more arguments.
+    Primitive(Primitive, Vec<Rc<Thunk<'a>>>),
+}
+
+#[derive(Clone)]
+enum Head<'a> {
+    Constant(&'a str),
+    /// A variable that is free in the whole expression, indexed from outside of it.
+    Free(Option<usize>),
+    Integer(Natural),
+    /// A primitive whose arguments are not integers it is defined for.
+    Primitive(Primitive),
+}
+
+enum Frame<'a> {
+    Argument(Rc<Thunk<'a>>),
+    Update(Rc<Thunk<'a>>),
+    /// Evaluates the arguments of a primitive one by one, with the integers so far.
+    Strict(Primitive, Vec<Rc<Thunk<'a>>>, Vec<Natural>),
+}
+
+#[derive(Clone)]
+enum Binding<'a> {
+    Thunk(Rc<Thunk<'a>>),
+    /// A binder that is being read back, by its depth.
+    Bound(usize),
+}
+
+#[derive(Clone, Default)]
+struct Environment<'a>(Option<Rc<Node<'a>>>);
+
+struct Node<'a> {
+    binding: Binding<'a>,
+    length: usize,
+    next: Environment<'a>,
+}
+
+impl<'a> Thunk<'a> {
+    fn new(
+        expression: &'a Expression,
+        environment: Environment<'a>,
+        live: &Rc<Cell<usize>>,
+    ) -> Rc<Thunk<'a>> {
+        live.set(live.get() + 1);
+        Rc::new(Thunk {
+            expression,
+            environment,
+            value: RefCell::new(None),
+            live: live.clone(),
+        })
+    }
+}
+
+impl<'a> Drop for Thunk<'a> {
+    fn drop(&mut self) {
+        self.live.set(self.live.get() - 1);
+    }
+}
+
+impl<'a> Environment<'a> {
+    fn len(&self) -> usize {
+        self.0.as_ref().map_or(0, |node| node.length)
+    }
+
+    fn pushed(&self, binding: Binding<'a>) -> Environment<'a> {
+        Environment(Some(Rc::new(Node {
+            binding,
+            length: self.len() + 1,
+            next: self.clone(),
+        })))
+    }
+
+    fn get(&self, index: usize) -> Option<&Binding<'a>> {
+        let mut node = self.0.as_ref()?;
+        for _ in 0..index {
+            node = node.next.0.as_ref()?;
+        }
+        Some(&node.binding)
+    }
+}
+
+/// Work left while reading a term back.
+enum ReadBack<'a> {
+    Expression(&'a Expression, Environment<'a>, usize),
+    Thunk(Rc<Thunk<'a>>, usize),
+    Value(Value<'a>, usize),
+    Abstraction(&'a Name),
+    Application,
+}
+
+/// Turns what the machine holds back into a term, at a depth of binders under which the
+/// variables free in the whole expression are shifted.
+///
+/// Thunks that have been evaluated are read back from their values rather than their
+/// expressions, so the result keeps the work they shared.
+fn read_back(task: ReadBack) -> Expression {
+    let mut tasks = vec![task];
+    let mut results = Vec::new();
+
+    while let Some(task) = tasks.pop() {
+        match task {
+            ReadBack::Expression(expression, environment, depth) => match expression {
+                Expression::Variable { index: Some(index) } => match environment.get(*index) {
+                    Some(Binding::Thunk(thunk)) => {
+                        tasks.push(ReadBack::Thunk(thunk.clone(), depth))
+                    }
+                    Some(Binding::Bound(level)) => results.push(Expression::Variable {
+                        index: Some(depth - 1 - level),
+                    }),
+                    None => results.push(Expression::Variable {
+                        index: Some(index - environment.len() + depth),
+                    }),
+                },
+
+                Expression::Variable { .. }
+                | Expression::Constant { .. }
+                | Expression::Integer { .. }
+                | Expression::Primitive { .. } => results.push(expression.clone()),
+
+                Expression::Abstraction { name, expression } => {
+                    tasks.push(ReadBack::Abstraction(name));
+                    tasks.push(ReadBack::Expression(
+                        expression,
+                        environment.pushed(Binding::Bound(depth)),
+                        depth + 1,
+                    ));
+                }
+
+                Expression::Application {
+                    applicand,
+                    argument,
+                } => {
+                    tasks.push(ReadBack::Application);
+                    tasks.push(ReadBack::Expression(argument, environment.clone(), depth));
+                    tasks.push(ReadBack::Expression(applicand, environment, depth));
+                }
+            },
+
+            ReadBack::Thunk(thunk, depth) => match *thunk.value.borrow() {
+                Some(ref value) => tasks.push(ReadBack::Value(value.clone(), depth)),
+                None => tasks.push(ReadBack::Expression(
+                    thunk.expression,
+                    thunk.environment.clone(),
+                    depth,
+                )),
+            },
+
+            ReadBack::Value(value, depth) => {
+                let (head, arguments) = match value {
+                    Value::Closure(name, body, environment) => {
+                        tasks.push(ReadBack::Abstraction(name));
+                        tasks.push(ReadBack::Expression(
+                            body,
+                            environment.pushed(Binding::Bound(depth)),
+                            depth + 1,
+                        ));
+                        continue;
+                    }
+                    Value::Integer(value) => (Expression::Integer { value }, Vec::new()),
+                    Value::Neutral(head, arguments) => {
+                        let head = match head {
+                            Head::Constant(name) => Expression::Constant {
+                                name: name.to_owned(),
+                            },
+                            Head::Free(index) => Expression::Variable {
+                                index: index.map(|index| index + depth),
+                            },
+                            Head::Integer(value) => Expression::Integer { value },
+                            Head::Primitive(primitive) => Expression::Primitive { primitive },
+                        };
+                        (head, arguments)
+                    }
+                    Value::Primitive(primitive, arguments) => {
+                        (Expression::Primitive { primitive }, arguments)
+                    }
+                };
+
+                results.push(head);
+                for argument in arguments.into_iter().rev() {
+                    tasks.push(ReadBack::Application);
+                    tasks.push(ReadBack::Thunk(argument, depth));
+                }
+            }
+
+            ReadBack::Abstraction(name) => {
+                let expression = results.pop().unwrap();
+                results.push(Expression::Abstraction {
+                    name: name.clone(),
+                    expression: box expression,
+                });
+            }
+
+            ReadBack::Application => {
+                let argument = results.pop().unwrap();
+                let applicand = results.pop().unwrap();
+                results.push(Expression::Application {
+                    applicand: box applicand,
+                    argument: box argument,
+                });
+            }
+        }
+    }
+
+    results.pop().unwrap()
+}
+
+/// Environments and thunks can form chains as long as the reduction that built them, so they
+/// are taken apart one link at a time rather than by recursive drops.
+impl<'a> Drop for Environment<'a> {
+    fn drop(&mut self) {
+        let mut environments = vec![self.0.take()];
+        let mut thunks = Vec::new();
+
+        loop {
+            if let Some(thunk) = thunks.pop() {
+                if let Ok(mut thunk) = Rc::try_unwrap(thunk) {
+                    let thunk: &mut Thunk = &mut thunk;
+                    environments.push(thunk.environment.0.take());
+                    match thunk.value.get_mut().take() {
+                        Some(Value::Closure(_, _, mut environment)) => {
+                            environments.push(environment.0.take())
+                        }
+                        Some(Value::Neutral(_, arguments))
+                        | Some(Value::Primitive(_, arguments)) => thunks.extend(arguments),
+                        Some(Value::Integer(..)) | None => {}
+                    }
+                }
+                continue;
+            }
+
+            match environments.pop() {
+                Some(Some(node)) => {
+                    if let Ok(mut node) = Rc::try_unwrap(node) {
+                        environments.push(node.next.0.take());
+                        let binding = mem::replace(&mut node.binding, Binding::Bound(0));
+                        if let Binding::Thunk(thunk) = binding {
+                            thunks.push(thunk);
+                        }
+                    }
+                }
+                Some(None) => {}
+                None => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ast::Options;
+    use lexer::Lexer;
+    use parser::parse;
+    use strategy::Strategy;
+
+    fn translate(source: &str) -> Expression {
+        let program = parse(&mut Lexer::new(source)).unwrap();
+        Expression::from_cst_program_with(
+            &program,
+            &Options {
+                open_terms: true,
+                ..Options::default()
+            },
+        ).unwrap()
+    }
+
+    #[test]
+    fn test_same_result_as_call_by_name() {
+        for source in &[
+            r"(\x y. y x) ((\z. z) (\z. z));",
+            r"(\x. x (\y. x)) ((\z. z) (\w. w));",
+            r"(\x. x x) (\f. f) (\y. a y);",
+            r"(\x. f x x) ((\y. y) b);",
+            r"let two = \f x. f (f x); two two;",
+        ] {
+            let expression = translate(source);
+            assert_eq!(
+                expression.clone().reduce(Strategy::CallByName).normalize(),
+                evaluate(&expression).normalize(),
+                "{}",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn test_read_back_values() {
+        // `x` was evaluated to apply it, so its value is read back instead of its expression.
+        let expression = translate(r"(\x. x (\y. x)) ((\z. z) (\w. w));");
+        assert_eq!(r"\y w. w", evaluate(&expression).to_string());
+
+        let expression = translate(r"(\x. f x x) ((\y. y) b);");
+        assert_eq!(r"f ((\y. y) b) ((\y. y) b)", evaluate(&expression).to_string());
+    }
+
+    #[test]
+    fn test_evaluate_with_limits() {
+        let divergent = translate(r"(\x. x x) (\x. x x);");
+        let limits = Limits {
+            steps: Some(10),
+            ..Limits::default()
+        };
+        assert_eq!(
+            Outcome::StepLimit(divergent.clone()),
+            evaluate_with_limits(&divergent, &limits)
+        );
+
+        let growing = translate(r"(\x. x x x) (\x. x x x);");
+        let limits = Limits {
+            size: Some(100),
+            ..Limits::default()
+        };
+        match evaluate_with_limits(&growing, &limits) {
+            Outcome::SizeLimit(expression) => assert!(expression.size() > 100),
+            outcome => panic!("{:?}", outcome),
+        }
+
+        // The stack stays shallow while the chain of successors grows.
+        let counting = translate(
+            r"let y = \f. (\x. f (x x)) (\x. f (x x));
+            let succ = \n f x. f (n f x);
+            y (\f n. f (succ n)) (\f x. x);",
+        );
+        match evaluate_with_limits(&counting, &limits) {
+            Outcome::SizeLimit(..) => (),
+            outcome => panic!("{:?}", outcome),
+        }
+    }
+}
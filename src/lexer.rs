@@ -1,89 +1,208 @@
 use std::iter::Peekable;
 use std::str::Chars;
-use token::{Token, TokenKind};
+use token::{LiteralError, Position, Span, Token, TokenKind};
+use unicode_xid::UnicodeXID;
 
+#[derive(Clone)]
 pub struct Lexer<'a> {
     source: Peekable<Chars<'a>>,
-    line: usize,
-    column: usize,
+    position: Position,
+    start: Position,
     state: LexerState,
+    /// Doc comment lines waiting for the next token.
+    doc: Option<String>,
+    input: &'a str,
+    lossless: bool,
+    /// Trivia waiting for the next token, when lossless.
+    trivia: Vec<Token>,
 }
 
+#[derive(Clone)]
 enum LexerState {
     Initial,
     Return(Option<TokenKind>),
     Word(Option<String>),
     Whitespace,
     FirstSlash,
+    SecondSlash,
+    ThirdSlash,
     Comment,
+    DocComment(Option<String>),
+    /// How many `/*` are still open.
+    BlockComment(usize),
     Number(Option<String>),
-    Character(Option<char>),
+    /// The contents so far, and the first error if there was one.
+    Character(Option<String>, Option<LiteralError>),
+    String(Option<String>, Option<LiteralError>),
 }
 
 impl<'a> Lexer<'a> {
     pub fn new(source: &'a str) -> Peekable<Lexer<'a>> {
+        Lexer::with_mode(source, false)
+    }
+
+    /// A lexer which keeps the text of every token, and attaches the whitespace and comments
+    /// before it as trivia, so that the source can be reproduced exactly.
+    pub fn lossless(source: &'a str) -> Peekable<Lexer<'a>> {
+        Lexer::with_mode(source, true)
+    }
+
+    fn with_mode(source: &'a str, lossless: bool) -> Peekable<Lexer<'a>> {
         Lexer {
             source: source.chars().peekable(),
-            line: 0,
-            column: 0,
+            position: Position::default(),
+            start: Position::default(),
             state: LexerState::Initial,
+            doc: None,
+            input: source,
+            lossless,
+            trivia: Vec::new(),
         }.peekable()
     }
 
+    /// Ends a stretch of trivia, keeping it when lossless.
+    fn skip(&mut self, kind: TokenKind) -> LexerState {
+        if self.lossless {
+            let span = Span::new(self.start, self.position);
+            let mut token = Token::new(Some(kind), span);
+            token.text = Some(self.text(span));
+            self.trivia.push(token);
+        }
+        LexerState::Initial
+    }
+
+    fn text(&self, span: Span) -> String {
+        self.input[span.start.offset..span.end.offset].to_owned()
+    }
+
     fn source_next(&mut self) -> Option<char> {
-        if let Some('\n') = self.source.peek() {
-            self.line += 1;
-            self.column = 0;
+        let c = self.source.next()?;
+        self.position.offset += c.len_utf8();
+        if c == '\n' {
+            self.position.line += 1;
+            self.position.column = 0;
         } else {
-            self.column += 1;
+            self.position.column += 1;
+        }
+        Some(c)
+    }
+
+    /// Reads what follows a backslash. A line break is left for the literal to report as
+    /// unterminated.
+    fn escape(&mut self) -> Result<char, LiteralError> {
+        let mut escape = "\\".to_owned();
+        let c = match self.source.peek() {
+            Some(&c) if c != '\n' => c,
+            _ => return Err(LiteralError::InvalidEscape(escape)),
+        };
+        self.source_next();
+        escape.push(c);
+
+        match c {
+            'n' => Ok('\n'),
+            't' => Ok('\t'),
+            'r' => Ok('\r'),
+            '0' => Ok('\0'),
+            '\\' | '\'' | '"' => Ok(c),
+            'u' => self.unicode_escape(escape),
+            _ => Err(LiteralError::InvalidEscape(escape)),
+        }
+    }
+
+    /// Reads the `{1F600}` of a `\u{1F600}` escape, which takes one to six hex digits.
+    fn unicode_escape(&mut self, mut escape: String) -> Result<char, LiteralError> {
+        if self.source.peek() != Some(&'{') {
+            return Err(LiteralError::InvalidEscape(escape));
+        }
+        self.source_next();
+        escape.push('{');
+
+        while let Some(&c) = self.source.peek() {
+            if !c.is_ascii_hexdigit() {
+                break;
+            }
+            self.source_next();
+            escape.push(c);
+        }
+        if self.source.peek() != Some(&'}') {
+            return Err(LiteralError::InvalidEscape(escape));
+        }
+        self.source_next();
+        escape.push('}');
+
+        let digits = &escape[3..escape.len() - 1];
+        let value = match digits.len() {
+            1..=6 => u32::from_str_radix(digits, 16).ok(),
+            _ => None,
+        };
+        match value.and_then(::std::char::from_u32) {
+            Some(c) => Ok(c),
+            None => Err(LiteralError::InvalidEscape(escape)),
         }
-        self.source.next()
     }
 }
 
+/// Unicode `XID_Start`, and `_`.
+fn is_identifier_start(c: char) -> bool {
+    c == '_' || c.is_xid_start()
+}
+
+/// Unicode `XID_Continue`, and primes as in `x'`.
+fn is_identifier_continue(c: char) -> bool {
+    c == '\'' || c.is_xid_continue()
+}
+
 impl<'a> Iterator for Lexer<'a> {
     type Item = Token;
 
     fn next(&mut self) -> Option<Token> {
         loop {
             self.state = match self.state {
-                LexerState::Initial => match self.source_next() {
-                    Some('(') => LexerState::Return(Some(TokenKind::LeftBracket)),
-                    Some(')') => LexerState::Return(Some(TokenKind::RightBracket)),
-                    Some('\\') => LexerState::Return(Some(TokenKind::Lambda)),
-                    Some('.') => LexerState::Return(Some(TokenKind::Dot)),
-                    Some('=') => LexerState::Return(Some(TokenKind::Equal)),
-                    Some(';') => LexerState::Return(Some(TokenKind::Semicolon)),
-                    Some('/') => LexerState::FirstSlash,
-                    Some(c) if c.is_ascii_whitespace() => LexerState::Whitespace,
-                    Some(c) if c.is_ascii_alphabetic() || c == '_' => {
-                        LexerState::Word(Some(c.to_string()))
+                LexerState::Initial => {
+                    self.start = self.position;
+                    match self.source_next() {
+                        Some('(') => LexerState::Return(Some(TokenKind::LeftBracket)),
+                        Some(')') => LexerState::Return(Some(TokenKind::RightBracket)),
+                        Some('\\') | Some('λ') => LexerState::Return(Some(TokenKind::Lambda)),
+                        Some('.') | Some('→') => LexerState::Return(Some(TokenKind::Dot)),
+                        Some('=') => LexerState::Return(Some(TokenKind::Equal)),
+                        Some(';') => LexerState::Return(Some(TokenKind::Semicolon)),
+                        Some(',') => LexerState::Return(Some(TokenKind::Comma)),
+                        Some('/') => LexerState::FirstSlash,
+                        Some(c) if c.is_ascii_whitespace() => LexerState::Whitespace,
+                        Some(c) if is_identifier_start(c) => LexerState::Word(Some(c.to_string())),
+                        Some('\'') => LexerState::Character(Some(String::new()), None),
+                        Some('"') => LexerState::String(Some(String::new()), None),
+                        Some(c) if c.is_ascii_digit() => LexerState::Number(Some(c.to_string())),
+                        Some(c) => LexerState::Return(Some(TokenKind::InvalidCharacter(c))),
+                        None => LexerState::Return(None),
                     }
-                    Some('\'') => LexerState::Character(None),
-                    Some(c) if c.is_ascii_digit() => LexerState::Number(Some(c.to_string())),
-                    Some(c) => LexerState::Return(Some(TokenKind::InvalidCharacter(c))),
-                    None => LexerState::Return(None),
-                },
+                }
 
                 LexerState::Return(ref mut kind) => {
                     let kind = kind.take();
                     self.state = LexerState::Initial;
-                    return Some(Token::new(kind, self.line, self.column - 1));
+                    let mut token = Token::new(kind, Span::new(self.start, self.position));
+                    token.doc = self.doc.take();
+                    if self.lossless {
+                        token.text = Some(self.text(token.span));
+                        token.trivia = ::std::mem::take(&mut self.trivia);
+                    }
+                    return Some(token);
                 }
 
                 LexerState::Word(ref mut word) => {
                     let mut word = word.take().unwrap();
                     match self.source.peek() {
-                        Some(&c) if c.is_ascii_alphanumeric() || c == '_' => {
+                        Some(&c) if is_identifier_continue(c) => {
                             word.push(c);
                             self.source_next();
                             LexerState::Word(Some(word))
                         }
 
-                        _ => LexerState::Return(Some(if word == "let" {
-                            TokenKind::Let
-                        } else {
-                            TokenKind::Identifier(word)
+                        _ => LexerState::Return(Some(match word.as_str() {
+                            "let" => TokenKind::Let,
+                            _ => TokenKind::Identifier(word),
                         })),
                     }
                 }
@@ -93,19 +212,81 @@ impl<'a> Iterator for Lexer<'a> {
                         self.source_next();
                         LexerState::Whitespace
                     }
-                    _ => LexerState::Initial,
+                    _ => self.skip(TokenKind::Whitespace),
                 },
 
                 LexerState::FirstSlash => match self.source.peek() {
-                    Some('/') => LexerState::Comment,
+                    Some('/') => {
+                        self.source_next();
+                        LexerState::SecondSlash
+                    }
+                    Some('*') => {
+                        self.source_next();
+                        LexerState::BlockComment(1)
+                    }
                     _ => LexerState::Return(Some(TokenKind::InvalidCharacter('/'))),
                 },
 
-                LexerState::Comment => match self.source_next() {
-                    Some('\n') => LexerState::Initial,
+                LexerState::SecondSlash => match self.source.peek() {
+                    Some('/') => {
+                        self.source_next();
+                        LexerState::ThirdSlash
+                    }
                     _ => LexerState::Comment,
                 },
 
+                // As in Rust, `////` starts an ordinary comment.
+                LexerState::ThirdSlash => match self.source.peek() {
+                    Some('/') => LexerState::Comment,
+                    _ => LexerState::DocComment(Some(String::new())),
+                },
+
+                // The line break is left as whitespace.
+                LexerState::Comment => match self.source.peek() {
+                    Some('\n') | None => self.skip(TokenKind::Comment),
+                    _ => {
+                        self.source_next();
+                        LexerState::Comment
+                    }
+                },
+
+                LexerState::DocComment(ref mut line) => {
+                    let mut line = line.take().unwrap();
+                    match self.source.peek() {
+                        Some('\n') | None => {
+                            let line = line.strip_prefix(' ').unwrap_or(&line);
+                            let doc = self.doc.get_or_insert_with(String::new);
+                            if !doc.is_empty() {
+                                doc.push('\n');
+                            }
+                            doc.push_str(line);
+                            self.skip(TokenKind::Comment)
+                        }
+                        Some(&c) => {
+                            self.source_next();
+                            line.push(c);
+                            LexerState::DocComment(Some(line))
+                        }
+                    }
+                }
+
+                LexerState::BlockComment(depth) => match self.source_next() {
+                    Some('*') if self.source.peek() == Some(&'/') => {
+                        self.source_next();
+                        if depth == 1 {
+                            self.skip(TokenKind::Comment)
+                        } else {
+                            LexerState::BlockComment(depth - 1)
+                        }
+                    }
+                    Some('/') if self.source.peek() == Some(&'*') => {
+                        self.source_next();
+                        LexerState::BlockComment(depth + 1)
+                    }
+                    Some(_) => LexerState::BlockComment(depth),
+                    None => LexerState::Return(Some(TokenKind::UnterminatedComment)),
+                },
+
                 LexerState::Number(ref mut number) => {
                     let mut number = number.take().unwrap();
                     match self.source.peek() {
@@ -119,23 +300,72 @@ impl<'a> Iterator for Lexer<'a> {
                     }
                 }
 
-                LexerState::Character(None) => match self.source.peek() {
-                    Some('\'') | None => {
-                        LexerState::Return(Some(TokenKind::InvalidCharacter('\'')))
-                    }
-                    Some(&character) => {
-                        self.source_next();
-                        LexerState::Character(Some(character))
+                // Errors are reported once the whole literal has been skipped, so that their span
+                // covers it. Character literals end at the line, string literals may span lines.
+                LexerState::Character(ref mut text, ref mut error) => {
+                    let mut text = text.take().unwrap();
+                    let error = error.take();
+                    match self.source.peek() {
+                        Some('\'') => {
+                            self.source_next();
+                            let mut chars = text.chars();
+                            LexerState::Return(Some(match (error, chars.next(), chars.next()) {
+                                (Some(error), ..) => TokenKind::InvalidLiteral(error),
+                                (None, Some(c), None) => TokenKind::Character(c),
+                                (None, None, _) => {
+                                    TokenKind::InvalidLiteral(LiteralError::EmptyCharacter)
+                                }
+                                (None, Some(_), Some(_)) => {
+                                    TokenKind::InvalidLiteral(LiteralError::LongCharacter)
+                                }
+                            }))
+                        }
+                        Some('\n') | None => LexerState::Return(Some(TokenKind::InvalidLiteral(
+                            LiteralError::UnterminatedCharacter,
+                        ))),
+                        Some(_) => {
+                            let error = match self.source_next() {
+                                Some('\\') => match self.escape() {
+                                    Ok(c) => {
+                                        text.push(c);
+                                        error
+                                    }
+                                    Err(escape) => error.or(Some(escape)),
+                                },
+                                c => {
+                                    text.extend(c);
+                                    error
+                                }
+                            };
+                            LexerState::Character(Some(text), error)
+                        }
                     }
-                },
+                }
 
-                LexerState::Character(Some(character)) => match self.source_next() {
-                    Some('\'') => LexerState::Return(Some(TokenKind::Character(character))),
-                    Some(character) => {
-                        LexerState::Return(Some(TokenKind::InvalidCharacter(character)))
+                LexerState::String(ref mut string, ref mut error) => {
+                    let mut string = string.take().unwrap();
+                    let error = error.take();
+                    match self.source_next() {
+                        Some('"') => LexerState::Return(Some(match error {
+                            Some(error) => TokenKind::InvalidLiteral(error),
+                            None => TokenKind::String(string),
+                        })),
+                        Some('\\') => match self.escape() {
+                            Ok(c) => {
+                                string.push(c);
+                                LexerState::String(Some(string), error)
+                            }
+                            Err(escape) => LexerState::String(Some(string), error.or(Some(escape))),
+                        },
+                        Some(c) => {
+                            string.push(c);
+                            LexerState::String(Some(string), error)
+                        }
+                        None => LexerState::Return(Some(TokenKind::InvalidLiteral(
+                            LiteralError::UnterminatedString,
+                        ))),
                     }
-                    None => LexerState::Return(None),
-                },
+                }
             }
         }
     }
@@ -145,33 +375,283 @@ impl<'a> Iterator for Lexer<'a> {
 mod test {
     use super::*;
 
+    fn span(start: (usize, usize, usize), end: (usize, usize, usize)) -> Span {
+        Span::new(
+            Position::new(start.0, start.1, start.2),
+            Position::new(end.0, end.1, end.2),
+        )
+    }
+
     #[test]
     fn lexer_test() {
-        let lexer = Lexer::new("(\\foo\nbarBaz_2000'*'//@@@@\n.)42^");
+        let lexer = Lexer::new("(\\foo\nbarBaz_2000 '*'//@@@@\n.)42^");
         let expecteds = vec![
-            Token::new(TokenKind::LeftBracket, 0, 0),
-            Token::new(TokenKind::Lambda, 0, 1),
-            Token::new(TokenKind::Identifier("foo".to_owned()), 0, 4),
-            Token::new(TokenKind::Identifier("barBaz_2000".to_owned()), 1, 10),
-            Token::new(TokenKind::Character('*'), 1, 13),
-            Token::new(TokenKind::Dot, 2, 0),
-            Token::new(TokenKind::RightBracket, 2, 1),
-            Token::new(TokenKind::Number("42".to_owned()), 2, 3),
-            Token::new(TokenKind::InvalidCharacter('^'), 2, 4),
-        ].into_iter();
-
-        for (result, expected) in lexer.zip(expecteds) {
-            assert_eq!(expected, result);
-        }
+            Token::new(TokenKind::LeftBracket, span((0, 0, 0), (1, 0, 1))),
+            Token::new(TokenKind::Lambda, span((1, 0, 1), (2, 0, 2))),
+            Token::new(
+                TokenKind::Identifier("foo".to_owned()),
+                span((2, 0, 2), (5, 0, 5)),
+            ),
+            Token::new(
+                TokenKind::Identifier("barBaz_2000".to_owned()),
+                span((6, 1, 0), (17, 1, 11)),
+            ),
+            Token::new(TokenKind::Character('*'), span((18, 1, 12), (21, 1, 15))),
+            Token::new(TokenKind::Dot, span((28, 2, 0), (29, 2, 1))),
+            Token::new(TokenKind::RightBracket, span((29, 2, 1), (30, 2, 2))),
+            Token::new(
+                TokenKind::Number("42".to_owned()),
+                span((30, 2, 2), (32, 2, 4)),
+            ),
+            Token::new(
+                TokenKind::InvalidCharacter('^'),
+                span((32, 2, 4), (33, 2, 5)),
+            ),
+            Token::new(None, span((33, 2, 5), (33, 2, 5))),
+        ];
 
-        let lexer = Lexer::new("''");
+        assert_eq!(expecteds, lexer.take(10).collect::<Vec<_>>());
+
+        let lexer = Lexer::new("'' x");
+        let expecteds = vec![
+            Token::new(
+                TokenKind::InvalidLiteral(LiteralError::EmptyCharacter),
+                span((0, 0, 0), (2, 0, 2)),
+            ),
+            Token::new(
+                TokenKind::Identifier("x".to_owned()),
+                span((3, 0, 3), (4, 0, 4)),
+            ),
+        ];
+
+        assert_eq!(expecteds, lexer.take(2).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_block_comments() {
+        let lexer = Lexer::new("x /* a /* b */ c */ y /*/ z */ /* /* */");
+        let expecteds = vec![
+            Token::new(
+                TokenKind::Identifier("x".to_owned()),
+                span((0, 0, 0), (1, 0, 1)),
+            ),
+            Token::new(
+                TokenKind::Identifier("y".to_owned()),
+                span((20, 0, 20), (21, 0, 21)),
+            ),
+            Token::new(TokenKind::UnterminatedComment, span((31, 0, 31), (39, 0, 39))),
+            Token::new(None, span((39, 0, 39), (39, 0, 39))),
+        ];
+
+        assert_eq!(expecteds, lexer.take(4).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_doc_comments() {
+        let source =
+            "/// The identity.\n///\n///  Indented.\n// Not documentation.\nlet\n//// x\ny";
+        let tokens = Lexer::new(source).take(3).collect::<Vec<_>>();
+
+        assert_eq!(Some(TokenKind::Let), tokens[0].kind);
+        assert_eq!(
+            Some("The identity.\n\n Indented.".to_owned()),
+            tokens[0].doc
+        );
+        assert_eq!(Some(TokenKind::Identifier("y".to_owned())), tokens[1].kind);
+        assert_eq!(None, tokens[1].doc);
+        assert_eq!(None, tokens[2].kind);
+    }
+
+    #[test]
+    fn test_unicode_identifiers() {
+        let lexer = Lexer::new("λf'. αβ_1 f' 'x'\nλé\u{301}.");
+        let identifier = |name: &str| TokenKind::Identifier(name.to_owned());
         let expecteds = vec![
-            Token::new(TokenKind::InvalidCharacter('\''), 0, 0),
-            Token::new(TokenKind::InvalidCharacter('\''), 0, 1),
-        ].into_iter();
+            Token::new(TokenKind::Lambda, span((0, 0, 0), (2, 0, 1))),
+            Token::new(identifier("f'"), span((2, 0, 1), (4, 0, 3))),
+            Token::new(TokenKind::Dot, span((4, 0, 3), (5, 0, 4))),
+            Token::new(identifier("αβ_1"), span((6, 0, 5), (12, 0, 9))),
+            Token::new(identifier("f'"), span((13, 0, 10), (15, 0, 12))),
+            Token::new(TokenKind::Character('x'), span((16, 0, 13), (19, 0, 16))),
+            Token::new(TokenKind::Lambda, span((20, 1, 0), (22, 1, 1))),
+            Token::new(identifier("é\u{301}"), span((22, 1, 1), (26, 1, 3))),
+            Token::new(TokenKind::Dot, span((26, 1, 3), (27, 1, 4))),
+        ];
 
-        for (result, expected) in lexer.zip(expecteds) {
-            assert_eq!(expected, result);
+        assert_eq!(expecteds, lexer.take(9).collect::<Vec<_>>());
+
+        // `·` continues identifiers but `²` does not, although it is numeric.
+        let kinds: Vec<_> = Lexer::new("λx → x·y x²")
+            .take_while(|token| token.kind.is_some())
+            .map(|token| token.kind.unwrap())
+            .collect();
+        assert_eq!(
+            vec![
+                TokenKind::Lambda,
+                identifier("x"),
+                TokenKind::Dot,
+                identifier("x·y"),
+                identifier("x"),
+                TokenKind::InvalidCharacter('²'),
+            ],
+            kinds
+        );
+
+        // Primes continue identifiers, so `f'a'` no longer applies `f` to a character.
+        let kinds: Vec<_> = Lexer::new("f'a' f 'a'")
+            .take_while(|token| token.kind.is_some())
+            .map(|token| token.kind.unwrap())
+            .collect();
+        assert_eq!(
+            vec![identifier("f'a'"), identifier("f"), TokenKind::Character('a')],
+            kinds
+        );
+    }
+
+    #[test]
+    fn test_character_escapes() {
+        let kinds = |source| {
+            Lexer::new(source)
+                .map(|token| token.kind)
+                .take_while(Option::is_some)
+                .collect::<Option<Vec<_>>>()
+                .unwrap()
+        };
+
+        assert_eq!(
+            vec![
+                TokenKind::Character('\n'),
+                TokenKind::Character('\t'),
+                TokenKind::Character('\\'),
+                TokenKind::Character('\''),
+                TokenKind::Character('😀'),
+                TokenKind::Character('😀'),
+            ],
+            kinds(r"'\n' '\t' '\\' '\'' '\u{1F600}' '😀'")
+        );
+        assert_eq!(
+            vec![TokenKind::String("\u{e9}\0".to_owned())],
+            kinds(r#""\u{e9}\0""#)
+        );
+
+        let invalid = |error| vec![TokenKind::InvalidLiteral(error)];
+        let escape = |escape: &str| invalid(LiteralError::InvalidEscape(escape.to_owned()));
+        assert_eq!(escape(r"\q"), kinds(r"'\q'"));
+        assert_eq!(escape(r"\u{d800}"), kinds(r"'\u{d800}'"));
+        assert_eq!(escape(r"\u{1234567}"), kinds(r"'\u{1234567}'"));
+        assert_eq!(escape(r"\u{}"), kinds(r"'\u{}'"));
+        assert_eq!(escape(r"\u"), kinds(r#""\u1""#));
+        assert_eq!(invalid(LiteralError::LongCharacter), kinds("'ab'"));
+        assert_eq!(
+            vec![
+                TokenKind::InvalidLiteral(LiteralError::UnterminatedCharacter),
+                TokenKind::Identifier("x".to_owned()),
+            ],
+            kinds("'a\nx")
+        );
+        assert_eq!(invalid(LiteralError::UnterminatedCharacter), kinds(r"'\"));
+        assert_eq!(invalid(LiteralError::UnterminatedString), kinds("\"a\nb"));
+    }
+
+    #[test]
+    fn test_multibyte_span() {
+        let lexer = Lexer::new("'é' x");
+        let expecteds = vec![
+            Token::new(TokenKind::Character('é'), span((0, 0, 0), (4, 0, 3))),
+            Token::new(
+                TokenKind::Identifier("x".to_owned()),
+                span((5, 0, 4), (6, 0, 5)),
+            ),
+        ];
+
+        assert_eq!(expecteds, lexer.take(2).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_string() {
+        let lexer = Lexer::new(r#""a \"b\"\n\\" "" "\q""#);
+        let expecteds = vec![
+            Token::new(
+                TokenKind::String("a \"b\"\n\\".to_owned()),
+                span((0, 0, 0), (13, 0, 13)),
+            ),
+            Token::new(
+                TokenKind::String(String::new()),
+                span((14, 0, 14), (16, 0, 16)),
+            ),
+            Token::new(
+                TokenKind::InvalidLiteral(LiteralError::InvalidEscape("\\q".to_owned())),
+                span((17, 0, 17), (21, 0, 21)),
+            ),
+            Token::new(None, span((21, 0, 21), (21, 0, 21))),
+        ];
+
+        assert_eq!(expecteds, lexer.take(4).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_unterminated_input() {
+        for source in &["// comment", "'a", "'", "'a\n", "\"a", "\"a\\"] {
+            let token = Lexer::new(source).find(|token| token.kind.is_none());
+            assert!(token.is_some());
         }
     }
+
+    #[test]
+    fn test_keywords() {
+        // `rec`, `and` and `in` are only keywords where the parser expects them.
+        let kinds: Vec<_> = Lexer::new("let rec x = y, inner and in x")
+            .take_while(|token| token.kind.is_some())
+            .map(|token| token.kind.unwrap())
+            .collect();
+        let identifier = |name: &str| TokenKind::Identifier(name.to_owned());
+        assert_eq!(
+            vec![
+                TokenKind::Let,
+                identifier("rec"),
+                identifier("x"),
+                TokenKind::Equal,
+                identifier("y"),
+                TokenKind::Comma,
+                identifier("inner"),
+                identifier("and"),
+                identifier("in"),
+                identifier("x"),
+            ],
+            kinds
+        );
+    }
+
+    #[test]
+    fn test_lossless() {
+        let source = "/// Doc\nx /* a /* b */ */\t// c\n";
+        let tokens: Vec<_> = Lexer::lossless(source).take(2).collect();
+
+        let trivia: Vec<_> = tokens[0]
+            .trivia
+            .iter()
+            .map(|token| (token.kind.clone().unwrap(), token.text.clone().unwrap()))
+            .collect();
+        assert_eq!(
+            vec![
+                (TokenKind::Comment, "/// Doc".to_owned()),
+                (TokenKind::Whitespace, "\n".to_owned()),
+            ],
+            trivia
+        );
+        assert_eq!(Some("x".to_owned()), tokens[0].text);
+        assert_eq!(Some("Doc".to_owned()), tokens[0].doc);
+
+        let trivia: Vec<_> = tokens[1]
+            .trivia
+            .iter()
+            .map(|token| token.text.clone().unwrap())
+            .collect();
+        assert_eq!(vec![" ", "/* a /* b */ */", "\t", "// c", "\n"], trivia);
+        assert_eq!(None, tokens[1].kind);
+        assert_eq!(Some(String::new()), tokens[1].text);
+
+        let token = Lexer::new(source).next().unwrap();
+        assert_eq!((None, 0), (token.text, token.trivia.len()));
+    }
 }
@@ -1,7 +1,18 @@
 #![feature(box_syntax, box_patterns, nll, if_while_or_patterns)]
 
+extern crate unicode_xid;
+
 pub mod ast;
 pub mod cst;
+pub mod decode;
+pub mod diagnostic;
+pub mod formatter;
+pub mod lazy;
 pub mod lexer;
+pub mod natural;
 pub mod parser;
+pub mod primitive;
+pub mod printer;
+pub mod scope;
+pub mod strategy;
 pub mod token;
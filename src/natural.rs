@@ -0,0 +1,232 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// An arbitrary-precision non-negative integer.
+///
+/// Stored as base 2³² digits from the least significant one, without leading zeros, so zero
+/// has no digits at all.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Default)]
+pub struct Natural(Vec<u32>);
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct InvalidNatural(pub String);
+
+impl Natural {
+    pub fn zero() -> Natural {
+        Natural(Vec::new())
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn to_u64(&self) -> Option<u64> {
+        match self.0.as_slice() {
+            [] => Some(0),
+            [low] => Some(u64::from(*low)),
+            [low, high] => Some(u64::from(*high) << 32 | u64::from(*low)),
+            _ => None,
+        }
+    }
+
+    pub fn add(&self, other: &Natural) -> Natural {
+        let mut digits = Vec::with_capacity(self.0.len().max(other.0.len()) + 1);
+        let mut carry = 0;
+        for i in 0..self.0.len().max(other.0.len()) {
+            let sum = u64::from(self.digit(i)) + u64::from(other.digit(i)) + carry;
+            digits.push(sum as u32);
+            carry = sum >> 32;
+        }
+        digits.push(carry as u32);
+        Natural::normalized(digits)
+    }
+
+    /// Subtracts `other`, or returns zero if it is larger.
+    pub fn saturating_sub(&self, other: &Natural) -> Natural {
+        if *self <= *other {
+            return Natural::zero();
+        }
+
+        let mut digits = Vec::with_capacity(self.0.len());
+        let mut borrow = 0;
+        for i in 0..self.0.len() {
+            let difference = i64::from(self.digit(i)) - i64::from(other.digit(i)) - borrow;
+            if difference < 0 {
+                digits.push((difference + (1 << 32)) as u32);
+                borrow = 1;
+            } else {
+                digits.push(difference as u32);
+                borrow = 0;
+            }
+        }
+        Natural::normalized(digits)
+    }
+
+    pub fn mul(&self, other: &Natural) -> Natural {
+        let mut digits = vec![0u32; self.0.len() + other.0.len()];
+        for (i, &a) in self.0.iter().enumerate() {
+            let mut carry = 0;
+            for (j, &b) in other.0.iter().enumerate() {
+                let product = u64::from(a) * u64::from(b) + u64::from(digits[i + j]) + carry;
+                digits[i + j] = product as u32;
+                carry = product >> 32;
+            }
+            digits[i + other.0.len()] = carry as u32;
+        }
+        Natural::normalized(digits)
+    }
+
+    /// Returns the quotient and the remainder, or `None` when dividing by zero.
+    pub fn div_rem(&self, other: &Natural) -> Option<(Natural, Natural)> {
+        if other.is_zero() {
+            return None;
+        }
+        if let [divisor] = other.0.as_slice() {
+            let (quotient, remainder) = self.div_rem_digit(*divisor);
+            return Some((quotient, Natural::from(u64::from(remainder))));
+        }
+
+        // Long division one bit at a time, which is plenty for the sizes terms deal with.
+        let mut quotient = vec![0u32; self.0.len()];
+        let mut remainder = Natural::zero();
+        for bit in (0..self.0.len() * 32).rev() {
+            remainder = remainder.add(&remainder);
+            if self.0[bit / 32] >> (bit % 32) & 1 == 1 {
+                remainder = remainder.add(&Natural::from(1));
+            }
+            if remainder >= *other {
+                remainder = remainder.saturating_sub(other);
+                quotient[bit / 32] |= 1 << (bit % 32);
+            }
+        }
+        Some((Natural::normalized(quotient), remainder))
+    }
+
+    fn div_rem_digit(&self, divisor: u32) -> (Natural, u32) {
+        let mut digits = vec![0u32; self.0.len()];
+        let mut remainder = 0u64;
+        for i in (0..self.0.len()).rev() {
+            let dividend = remainder << 32 | u64::from(self.0[i]);
+            digits[i] = (dividend / u64::from(divisor)) as u32;
+            remainder = dividend % u64::from(divisor);
+        }
+        (Natural::normalized(digits), remainder as u32)
+    }
+
+    fn digit(&self, i: usize) -> u32 {
+        self.0.get(i).cloned().unwrap_or(0)
+    }
+
+    fn normalized(mut digits: Vec<u32>) -> Natural {
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+        Natural(digits)
+    }
+}
+
+impl From<u64> for Natural {
+    fn from(n: u64) -> Natural {
+        Natural::normalized(vec![n as u32, (n >> 32) as u32])
+    }
+}
+
+impl Ord for Natural {
+    fn cmp(&self, other: &Natural) -> Ordering {
+        self.0
+            .len()
+            .cmp(&other.0.len())
+            .then_with(|| self.0.iter().rev().cmp(other.0.iter().rev()))
+    }
+}
+
+impl PartialOrd for Natural {
+    fn partial_cmp(&self, other: &Natural) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Parses decimal digits.
+impl FromStr for Natural {
+    type Err = InvalidNatural;
+
+    fn from_str(s: &str) -> Result<Natural, InvalidNatural> {
+        if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(InvalidNatural(s.to_owned()));
+        }
+
+        let ten = Natural::from(10);
+        Ok(s.bytes().fold(Natural::zero(), |n, b| {
+            n.mul(&ten).add(&Natural::from(u64::from(b - b'0')))
+        }))
+    }
+}
+
+impl Display for Natural {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        // Nine decimal digits at a time, from the least significant group.
+        let mut groups = Vec::new();
+        let mut n = self.clone();
+        while !n.is_zero() {
+            let (quotient, remainder) = n.div_rem_digit(1_000_000_000);
+            groups.push(remainder);
+            n = quotient;
+        }
+
+        let mut digits = match groups.pop() {
+            Some(group) => group.to_string(),
+            None => "0".to_owned(),
+        };
+        for group in groups.iter().rev() {
+            digits.push_str(&format!("{:09}", group));
+        }
+        f.pad_integral(true, "", &digits)
+    }
+}
+
+impl Display for InvalidNatural {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "Invalid natural number \"{}\"", self.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn natural(s: &str) -> Natural {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        for s in &["0", "7", "4294967296", "18446744073709551616", "1000000000000000000001"] {
+            assert_eq!(*s, natural(s).to_string());
+        }
+        assert_eq!(Some(4294967296), natural("4294967296").to_u64());
+        assert_eq!(None, natural("18446744073709551616").to_u64());
+        assert!("12a".parse::<Natural>().is_err());
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let a = natural("123456789012345678901234567890");
+        let b = natural("987654321098765432109876543210");
+        assert_eq!(natural("1111111110111111111011111111100"), a.add(&b));
+        assert_eq!(natural("864197532086419753208641975320"), b.saturating_sub(&a));
+        assert_eq!(Natural::zero(), a.saturating_sub(&b));
+        assert_eq!(
+            natural("121932631137021795226185032733622923332237463801111263526900"),
+            a.mul(&b)
+        );
+        assert_eq!(
+            Some((natural("8"), natural("9000000000900000000090"))),
+            b.div_rem(&a)
+        );
+        assert_eq!(Some((a.clone(), Natural::zero())), a.mul(&b).div_rem(&b));
+        assert_eq!(None, a.div_rem(&Natural::zero()));
+        assert!(a < b);
+    }
+}
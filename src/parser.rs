@@ -1,190 +1,692 @@
 use cst::{
-    AbstractionExpression, ApplicationExpression, Character, Expression, ExpressionStatement,
-    Identifier, LetStatement, Number, Program, Statement, VariableExpression,
+    AbstractionExpression, ApplicationExpression, Binding, Character, ErrorStatement, Expression,
+    ExpressionStatement, Identifier, LetExpression, LetRecStatement, LetStatement, Number, Program,
+    Statement, StringLiteral, VariableExpression,
 };
 use lexer::Lexer;
+use natural::Natural;
+use std::fmt;
+use std::fmt::{Display, Formatter};
 use std::iter::Peekable;
 use std::result;
-use token::{Token, TokenKind};
+use token::{Span, Token, TokenKind, TokenType};
+
+type Result<T> = result::Result<T, ParseError>;
+
+/// What an expression, and so any statement, can start with.
+static EXPRESSION_START: &[TokenType] = &[
+    TokenType::Lambda,
+    TokenType::LeftBracket,
+    TokenType::Let,
+    TokenType::Identifier,
+    TokenType::Number,
+    TokenType::Character,
+    TokenType::String,
+];
+
+/// The innermost `let` binding an expression is part of, which decides whether `in` and `and`
+/// end the expression or are variables.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+enum Context {
+    /// Outside of any binding, and inside brackets.
+    Free,
+    /// In the binding of a `let`, which `in` ends.
+    Let,
+    /// In the binding of a `let rec`, which `in` or `and` ends.
+    LetRec,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ParseError {
+    UnexpectedToken {
+        found: Box<Token>,
+        expected: Vec<TokenType>,
+        span: Span,
+    },
+    UnexpectedEndOfInput {
+        expected: Vec<TokenType>,
+        span: Span,
+    },
+    InvalidLiteral {
+        found: Box<Token>,
+        span: Span,
+    },
+    InvalidCharacter {
+        found: Box<Token>,
+        span: Span,
+    },
+    UnterminatedComment {
+        span: Span,
+    },
+    /// An expression statement before the last one, whose value would be lost.
+    MisplacedExpression {
+        span: Span,
+    },
+}
+
+impl ParseError {
+    fn unexpected(found: Token, expected: &[TokenType]) -> ParseError {
+        let span = found.span;
+        match found.kind {
+            None => ParseError::UnexpectedEndOfInput {
+                expected: expected.to_vec(),
+                span,
+            },
+            Some(TokenKind::InvalidCharacter(..)) => ParseError::InvalidCharacter {
+                found: box found,
+                span,
+            },
+            Some(TokenKind::InvalidLiteral(..)) => ParseError::InvalidLiteral {
+                found: box found,
+                span,
+            },
+            Some(TokenKind::UnterminatedComment) => ParseError::UnterminatedComment { span },
+            Some(..) => ParseError::UnexpectedToken {
+                found: box found,
+                expected: expected.to_vec(),
+                span,
+            },
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        match self {
+            ParseError::UnexpectedToken { span, .. }
+            | ParseError::UnexpectedEndOfInput { span, .. }
+            | ParseError::InvalidLiteral { span, .. }
+            | ParseError::InvalidCharacter { span, .. }
+            | ParseError::UnterminatedComment { span }
+            | ParseError::MisplacedExpression { span } => *span,
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            ParseError::UnexpectedToken {
+                found: box Token {
+                    kind: Some(kind), ..
+                },
+                expected,
+                ..
+            } => write!(f, "Expected {}, found {}", Expected(expected), kind),
+            ParseError::UnexpectedToken { expected, .. }
+            | ParseError::UnexpectedEndOfInput { expected, .. } => {
+                write!(f, "Expected {}, found end of input", Expected(expected))
+            }
+            ParseError::InvalidLiteral {
+                found: box Token {
+                    kind: Some(TokenKind::InvalidLiteral(error)),
+                    ..
+                },
+                ..
+            } => error.fmt(f),
+            ParseError::InvalidLiteral {
+                found: box Token {
+                    kind: Some(kind), ..
+                },
+                ..
+            } => write!(f, "Invalid literal {}", kind),
+            ParseError::InvalidCharacter {
+                found: box Token {
+                    kind: Some(TokenKind::InvalidCharacter(c)),
+                    ..
+                },
+                ..
+            } => write!(f, "Invalid character '{}'", c),
+            ParseError::InvalidLiteral { .. } | ParseError::InvalidCharacter { .. } => {
+                write!(f, "Invalid token")
+            }
+            ParseError::UnterminatedComment { .. } => write!(f, "Unterminated block comment"),
+            ParseError::MisplacedExpression { .. } => {
+                write!(f, "Only the last statement can be an expression")
+            }
+        }
+    }
+}
+
+struct Expected<'a>(&'a [TokenType]);
+
+impl<'a> Display for Expected<'a> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let Expected(expected) = self;
+        for (i, token_type) in expected.iter().enumerate() {
+            if i > 0 {
+                f.write_str(if i + 1 == expected.len() { " or " } else { ", " })?;
+            }
+            token_type.fmt(f)?;
+        }
+        Ok(())
+    }
+}
 
-type Result<T> = result::Result<T, String>;
+/// Takes the next token, treating an exhausted lexer like an end-of-input token.
+fn next_token(lexer: &mut Peekable<Lexer>, expected: &[TokenType]) -> Result<Token> {
+    lexer
+        .next()
+        .ok_or_else(|| ParseError::UnexpectedEndOfInput {
+            expected: expected.to_vec(),
+            span: Span::default(),
+        })
+}
+
+fn peek_kind<'a>(lexer: &'a mut Peekable<Lexer>) -> Option<&'a TokenKind> {
+    lexer.peek().and_then(|token| token.kind.as_ref())
+}
+
+/// Reports the next token as unexpected without consuming it, so that recovery can resume there.
+fn unexpected(lexer: &mut Peekable<Lexer>, expected: &[TokenType]) -> ParseError {
+    match lexer.peek() {
+        Some(found) => ParseError::unexpected(found.clone(), expected),
+        None => ParseError::UnexpectedEndOfInput {
+            expected: expected.to_vec(),
+            span: Span::default(),
+        },
+    }
+}
 
-static UNEXPECTED_NONE: &str = "Unexpected None";
+fn expect_token(lexer: &mut Peekable<Lexer>, expected: TokenType) -> Result<Token> {
+    if peek_kind(lexer).and_then(TokenKind::token_type) == Some(expected) {
+        next_token(lexer, &[expected])
+    } else {
+        Err(unexpected(lexer, &[expected]))
+    }
+}
+
+/// Expects a token and keeps it in `tokens`, returning its span.
+fn expect(
+    lexer: &mut Peekable<Lexer>,
+    expected: TokenType,
+    tokens: &mut Vec<Token>,
+) -> Result<Span> {
+    let token = expect_token(lexer, expected)?;
+    let span = token.span;
+    keep(tokens, token);
+    Ok(span)
+}
 
-fn expect(lexer: &mut Peekable<Lexer>, expected: &TokenKind) -> Result<()> {
-    match lexer.next().unwrap_or_else(|| panic!(UNEXPECTED_NONE)) {
-        Token {
-            kind: Some(ref found),
-            ..
-        } if expected == found =>
-        {
-            Ok(())
+/// Whether the next token is the identifier `word`.
+fn peek_word(lexer: &mut Peekable<Lexer>, word: &str) -> bool {
+    match peek_kind(lexer) {
+        Some(TokenKind::Identifier(name)) => name == word,
+        _ => false,
+    }
+}
+
+/// Expects `rec`, `and` or `in`, which the lexer reads as identifiers, and keeps it in `tokens`
+/// as a keyword.
+fn expect_keyword(
+    lexer: &mut Peekable<Lexer>,
+    expected: TokenType,
+    tokens: &mut Vec<Token>,
+) -> Result<Span> {
+    let (word, kind) = match expected {
+        TokenType::Rec => ("rec", TokenKind::Rec),
+        TokenType::And => ("and", TokenKind::And),
+        TokenType::In => ("in", TokenKind::In),
+        _ => unreachable!(),
+    };
+    if !peek_word(lexer, word) {
+        return Err(unexpected(lexer, &[expected]));
+    }
+
+    let mut token = next_token(lexer, &[expected])?;
+    let span = token.span;
+    token.kind = Some(kind);
+    keep(tokens, token);
+    Ok(span)
+}
+
+/// Keeps a token in a CST node if it came from `Lexer::lossless`.
+fn keep(tokens: &mut Vec<Token>, token: Token) {
+    if token.text.is_some() {
+        tokens.push(token);
+    }
+}
+
+/// Skips tokens up to and including the next `;`, keeping them in `tokens`, and returns the span
+/// of the last one skipped before it.
+fn synchronize(lexer: &mut Peekable<Lexer>, tokens: &mut Vec<Token>) -> Option<Span> {
+    let mut last = None;
+    loop {
+        match peek_kind(lexer) {
+            None => return last,
+            Some(TokenKind::Semicolon) => {
+                keep(tokens, lexer.next().unwrap());
+                return last;
+            }
+            Some(..) => {
+                let token = lexer.next().unwrap();
+                last = Some(token.span);
+                keep(tokens, token);
+            }
         }
-        found => Err(format!("Expected {}, found {}", expected, found)),
     }
 }
 
 pub fn parse(lexer: &mut Peekable<Lexer>) -> Result<Program> {
+    let (program, errors) = parse_with_recovery(lexer);
+    match errors.into_iter().next() {
+        Some(error) => Err(error),
+        None => Ok(program),
+    }
+}
+
+/// Parses the whole input, resynchronizing at the next `;` after each syntax error.
+///
+/// Statements that could not be parsed are kept in the program as `Statement::Error`.
+pub fn parse_with_recovery(lexer: &mut Peekable<Lexer>) -> (Program, Vec<ParseError>) {
     let mut statements = Vec::new();
+    let mut errors = Vec::new();
+
+    while peek_kind(lexer).is_some() {
+        // Only the last statement is evaluated, so an expression before it would be lost.
+        if let Some(Statement::Expression(statement)) = statements.last() {
+            errors.push(ParseError::MisplacedExpression {
+                span: statement.span,
+            });
+        }
+
+        let start = lexer.peek().map(|token| token.span).unwrap_or_default();
+        let replay = lexer.clone();
+        match parse_statement(lexer) {
+            Ok(statement) => statements.push(statement),
+            Err(error) => {
+                // The tokens taken by the failed statement are lost with it, so they are read
+                // again from a copy of the lexer.
+                let resume = lexer.peek().map_or(usize::MAX, |token| token.span.start.offset);
+                let mut tokens = Vec::new();
+                for token in replay.take_while(|token| token.span.start.offset < resume) {
+                    keep(&mut tokens, token);
+                }
+                let end = synchronize(lexer, &mut tokens).unwrap_or_else(|| error.span());
+                let mut statement = ErrorStatement::new(start.to(end));
+                statement.tokens = tokens;
+                statements.push(Statement::from(statement));
+                errors.push(error);
+            }
+        }
+    }
+
+    // A program is evaluated for its last statement, so it has to be an expression.
+    match statements.last() {
+        Some(Statement::Expression(..)) | Some(Statement::Error(..)) => (),
+        _ => errors.push(unexpected(lexer, EXPRESSION_START)),
+    }
 
-    while let Some(TokenKind::Lambda)
-    | Some(TokenKind::LeftBracket)
-    | Some(TokenKind::Let)
-    | Some(TokenKind::Identifier(..))
-    | Some(TokenKind::Number(..))
-    | Some(TokenKind::Character(..)) =
-        lexer.peek().unwrap_or_else(|| panic!(UNEXPECTED_NONE)).kind
-    {
-        statements.push(parse_statement(lexer)?)
+    // The end of input carries the trivia after the last statement. Without any statement, which
+    // is already an error, it is kept in an error statement of its own.
+    if let Some(token) = lexer.next() {
+        if statements.is_empty() {
+            statements.push(Statement::from(ErrorStatement::new(token.span)));
+        }
+        keep(statements.last_mut().unwrap().tokens_mut(), token);
     }
 
-    Ok(Program(statements))
+    (Program(statements), errors)
 }
 
 fn parse_statement(lexer: &mut Peekable<Lexer>) -> Result<Statement> {
-    let token = lexer.peek().unwrap_or_else(|| panic!(UNEXPECTED_NONE));
-
-    let result = match token.kind {
+    let mut result = match peek_kind(lexer) {
         Some(TokenKind::Lambda)
         | Some(TokenKind::LeftBracket)
         | Some(TokenKind::Identifier(..))
         | Some(TokenKind::Number(..))
-        | Some(TokenKind::Character(..)) => {
+        | Some(TokenKind::Character(..))
+        | Some(TokenKind::String(..)) => {
             Statement::from(ExpressionStatement::new(parse_expression(lexer)?))
         }
 
-        Some(TokenKind::Let) => Statement::from(parse_let(lexer)?),
+        Some(TokenKind::Let) => parse_let(lexer)?,
 
-        _ => {
-            return Err(format!(
-                "Expected '\\', '(', 'let' or identifier, found {}",
-                token
-            ))
-        }
+        _ => return Err(unexpected(lexer, EXPRESSION_START)),
     };
-    expect(lexer, &TokenKind::Semicolon)?;
+    expect(lexer, TokenType::Semicolon, result.tokens_mut())?;
     Ok(result)
 }
 
 pub fn parse_expression(lexer: &mut Peekable<Lexer>) -> Result<Expression> {
-    let token = lexer.peek().unwrap_or_else(|| panic!(UNEXPECTED_NONE));
+    parse_expression_in(lexer, Context::Free)
+}
 
-    match token.kind {
-        Some(TokenKind::Lambda) => Ok(Expression::from(parse_abstraction(lexer)?)),
+fn parse_expression_in(lexer: &mut Peekable<Lexer>, context: Context) -> Result<Expression> {
+    match peek_kind(lexer) {
+        Some(TokenKind::Lambda) => Ok(Expression::from(parse_abstraction(lexer, context)?)),
+        Some(TokenKind::Let) => Ok(Expression::from(parse_let_expression(lexer, context)?)),
         Some(TokenKind::LeftBracket)
         | Some(TokenKind::Identifier(..))
         | Some(TokenKind::Character(..))
-        | Some(TokenKind::Number(..)) => Ok(Expression::from(parse_application(lexer)?)),
-        _ => Err(format!("Expected '\\', '(' or identifier, found {}", token)),
+        | Some(TokenKind::String(..))
+        | Some(TokenKind::Number(..)) => Ok(Expression::from(parse_application(lexer, context)?)),
+        _ => Err(unexpected(lexer, EXPRESSION_START)),
     }
 }
 
-fn parse_abstraction(lexer: &mut Peekable<Lexer>) -> Result<AbstractionExpression> {
-    expect(lexer, &TokenKind::Lambda)?;
+fn parse_abstraction(
+    lexer: &mut Peekable<Lexer>,
+    context: Context,
+) -> Result<AbstractionExpression> {
+    let mut tokens = Vec::new();
+    let start = expect(lexer, TokenType::Lambda, &mut tokens)?;
     let parameters = parse_parameters(lexer)?;
-    expect(lexer, &TokenKind::Dot)?;
-    let expression = parse_expression(lexer)?;
-    Ok(AbstractionExpression::new(parameters, expression))
+    expect(lexer, TokenType::Dot, &mut tokens)?;
+    let expression = parse_expression_in(lexer, context)?;
+    let span = start.to(expression.span());
+    let mut abstraction = AbstractionExpression::new(parameters, expression, span);
+    abstraction.tokens = tokens;
+    Ok(abstraction)
 }
 
 fn parse_parameters(lexer: &mut Peekable<Lexer>) -> Result<Vec<Identifier>> {
     let mut parameters = Vec::new();
-    while let Some(TokenKind::Identifier(..)) =
-        lexer.peek().unwrap_or_else(|| panic!(UNEXPECTED_NONE)).kind
-    {
+    while let Some(TokenKind::Identifier(..)) = peek_kind(lexer) {
         parameters.push(parse_identifier(lexer)?);
     }
     Ok(parameters)
 }
 
-fn parse_application(lexer: &mut Peekable<Lexer>) -> Result<ApplicationExpression> {
+fn parse_application(
+    lexer: &mut Peekable<Lexer>,
+    context: Context,
+) -> Result<ApplicationExpression> {
     let mut expressions = Vec::new();
+    let mut tokens = Vec::new();
+    let mut span: Option<Span> = None;
     loop {
-        expressions.push(
-            match lexer.peek().unwrap_or_else(|| panic!(UNEXPECTED_NONE)).kind {
-                Some(TokenKind::Identifier(..)) => {
-                    Expression::from(VariableExpression::new(parse_identifier(lexer)?))
-                }
+        let ends = !expressions.is_empty()
+            && (context >= Context::Let && peek_word(lexer, "in")
+                || context == Context::LetRec && peek_word(lexer, "and"));
+        if ends {
+            break;
+        }
 
-                Some(TokenKind::Number(..)) => Expression::from(parse_number(lexer)?),
+        let (outer, expression) = match peek_kind(lexer) {
+            Some(TokenKind::Identifier(..)) => spanned(Expression::from(VariableExpression::new(
+                parse_identifier(lexer)?,
+            ))),
 
-                Some(TokenKind::Character(..)) => Expression::from(parse_character(lexer)?),
+            Some(TokenKind::Number(..)) => spanned(Expression::from(parse_number(lexer)?)),
 
-                Some(TokenKind::LeftBracket) => {
-                    expect(lexer, &TokenKind::LeftBracket)?;
-                    let expression = parse_expression(lexer)?;
-                    expect(lexer, &TokenKind::RightBracket)?;
-                    expression
-                }
+            Some(TokenKind::Character(..)) => spanned(Expression::from(parse_character(lexer)?)),
 
-                Some(TokenKind::Lambda) => Expression::from(parse_abstraction(lexer)?),
+            Some(TokenKind::String(..)) => spanned(Expression::from(parse_string(lexer)?)),
 
-                _ => break,
-            },
-        );
+            // The brackets belong to the application even though the inner node forgets them.
+            Some(TokenKind::LeftBracket) => {
+                let start = expect(lexer, TokenType::LeftBracket, &mut tokens)?;
+                let expression = parse_expression(lexer)?;
+                let end = expect(lexer, TokenType::RightBracket, &mut tokens)?;
+                (start.to(end), expression)
+            }
+
+            Some(TokenKind::Lambda) => {
+                spanned(Expression::from(parse_abstraction(lexer, context)?))
+            }
+
+            Some(TokenKind::Let) => {
+                spanned(Expression::from(parse_let_expression(lexer, context)?))
+            }
+
+            _ => break,
+        };
+        span = Some(span.unwrap_or(outer).to(outer));
+        expressions.push(expression);
     }
-    Ok(ApplicationExpression::new(expressions))
+    let mut application = ApplicationExpression::new(expressions, span.unwrap_or_default());
+    application.tokens = tokens;
+    Ok(application)
+}
+
+fn spanned(expression: Expression) -> (Span, Expression) {
+    (expression.span(), expression)
 }
 
 fn parse_identifier(lexer: &mut Peekable<Lexer>) -> Result<Identifier> {
-    let token = lexer.next().unwrap_or_else(|| panic!(UNEXPECTED_NONE));
+    let token = expect_token(lexer, TokenType::Identifier)?;
+
+    let mut identifier = match token.kind {
+        Some(TokenKind::Identifier(ref identifier)) => {
+            Identifier::new(identifier.as_str(), token.span)
+        }
+        _ => return Err(ParseError::unexpected(token, &[TokenType::Identifier])),
+    };
+    keep(&mut identifier.tokens, token);
+    Ok(identifier)
+}
+
+/// Parses a `let` or `let rec` statement, or the statement of a `let` expression if the
+/// bindings are followed by `in`.
+fn parse_let(lexer: &mut Peekable<Lexer>) -> Result<Statement> {
+    let mut tokens = Vec::new();
+    let mut token = expect_token(lexer, TokenType::Let)?;
+    let (start, doc) = (token.span, token.doc.take());
+    keep(&mut tokens, token);
+
+    if is_recursive(lexer) {
+        let bindings = parse_recursive_bindings(lexer, &mut tokens)?;
+        if peek_word(lexer, "in") {
+            let expression = parse_in(lexer, start, tokens, bindings, true, Context::Free)?;
+            return Ok(Statement::from(ExpressionStatement::new(expression)));
+        }
+
+        let span = start.to(bindings[bindings.len() - 1].span);
+        let mut statement = LetRecStatement::new(bindings, span);
+        statement.doc = doc;
+        statement.tokens = tokens;
+        return Ok(Statement::from(statement));
+    }
+
+    let binding = parse_binding(lexer, Context::Let)?;
+    if peek_kind(lexer) == Some(&TokenKind::Comma) || peek_word(lexer, "in") {
+        let bindings = parse_bindings(lexer, &mut tokens, binding, Context::Free)?;
+        let expression = parse_in(lexer, start, tokens, bindings, false, Context::Free)?;
+        return Ok(Statement::from(ExpressionStatement::new(expression)));
+    }
+
+    let Binding {
+        variable,
+        expression,
+        tokens: equal,
+        ..
+    } = binding;
+    let span = start.to(expression.span());
+    let mut statement = LetStatement::new(variable, expression, span);
+    statement.doc = doc;
+    statement.tokens = tokens;
+    statement.tokens.extend(equal);
+    Ok(Statement::from(statement))
+}
+
+fn parse_let_expression(lexer: &mut Peekable<Lexer>, context: Context) -> Result<LetExpression> {
+    let mut tokens = Vec::new();
+    let start = expect(lexer, TokenType::Let, &mut tokens)?;
+    if is_recursive(lexer) {
+        let bindings = parse_recursive_bindings(lexer, &mut tokens)?;
+        parse_in(lexer, start, tokens, bindings, true, context)
+    } else {
+        let binding = parse_binding(lexer, Context::Let.max(context))?;
+        let bindings = parse_bindings(lexer, &mut tokens, binding, context)?;
+        parse_in(lexer, start, tokens, bindings, false, context)
+    }
+}
+
+/// Whether the `let` just taken is followed by `rec` and a binding, so that `let rec = x;` still
+/// binds `rec`.
+fn is_recursive(lexer: &mut Peekable<Lexer>) -> bool {
+    if !peek_word(lexer, "rec") {
+        return false;
+    }
+    let mut ahead = lexer.clone();
+    ahead.next();
+    matches!(peek_kind(&mut ahead), Some(TokenKind::Identifier(..)))
+}
+
+/// Parses the bindings after the first one, separated by `,`, of a `let` in `context`.
+fn parse_bindings(
+    lexer: &mut Peekable<Lexer>,
+    tokens: &mut Vec<Token>,
+    first: Binding,
+    context: Context,
+) -> Result<Vec<Binding>> {
+    let mut bindings = vec![first];
+    while let Some(TokenKind::Comma) = peek_kind(lexer) {
+        expect(lexer, TokenType::Comma, tokens)?;
+        bindings.push(parse_binding(lexer, Context::Let.max(context))?);
+    }
+    Ok(bindings)
+}
 
-    match token.kind {
-        Some(TokenKind::Identifier(identifier)) => Ok(Identifier::new(identifier.as_str())),
-        _ => Err(format!("Expected identifier, found {}", token)),
+/// Parses `rec` and the bindings after it, separated by `and`.
+fn parse_recursive_bindings(
+    lexer: &mut Peekable<Lexer>,
+    tokens: &mut Vec<Token>,
+) -> Result<Vec<Binding>> {
+    expect_keyword(lexer, TokenType::Rec, tokens)?;
+    let mut bindings = vec![parse_binding(lexer, Context::LetRec)?];
+    while peek_word(lexer, "and") {
+        expect_keyword(lexer, TokenType::And, tokens)?;
+        bindings.push(parse_binding(lexer, Context::LetRec)?);
     }
+    Ok(bindings)
 }
 
-fn parse_let(lexer: &mut Peekable<Lexer>) -> Result<LetStatement> {
-    expect(lexer, &TokenKind::Let)?;
+/// Parses the `in` and the body of a `let` expression in `context`.
+fn parse_in(
+    lexer: &mut Peekable<Lexer>,
+    start: Span,
+    mut tokens: Vec<Token>,
+    bindings: Vec<Binding>,
+    recursive: bool,
+    context: Context,
+) -> Result<LetExpression> {
+    expect_keyword(lexer, TokenType::In, &mut tokens)?;
+    let expression = parse_expression_in(lexer, context)?;
+    let span = start.to(expression.span());
+    let mut let_expression = LetExpression::new(bindings, expression, span);
+    let_expression.recursive = recursive;
+    let_expression.tokens = tokens;
+    Ok(let_expression)
+}
+
+fn parse_binding(lexer: &mut Peekable<Lexer>, context: Context) -> Result<Binding> {
+    let mut tokens = Vec::new();
     let variable = parse_identifier(lexer)?;
-    expect(lexer, &TokenKind::Equal)?;
-    let expression = parse_expression(lexer)?;
-    Ok(LetStatement::new(variable, expression))
+    expect(lexer, TokenType::Equal, &mut tokens)?;
+    let expression = parse_expression_in(lexer, context)?;
+    let mut binding = Binding::new(variable, expression);
+    binding.tokens = tokens;
+    Ok(binding)
 }
 
 fn parse_number(lexer: &mut Peekable<Lexer>) -> Result<Number> {
-    let token = lexer.next().unwrap_or_else(|| panic!(UNEXPECTED_NONE));
+    let token = expect_token(lexer, TokenType::Number)?;
 
-    match token.kind {
-        Some(TokenKind::Number(number)) => Ok(Number::new(number.as_str())),
-        _ => Err(format!("Expected number, found {}", token)),
-    }
+    let mut number = match token.kind {
+        Some(TokenKind::Number(ref number)) if number.parse::<Natural>().is_ok() => {
+            Number::new(number.as_str(), token.span)
+        }
+        Some(TokenKind::Number(..)) => {
+            return Err(ParseError::InvalidLiteral {
+                span: token.span,
+                found: box token,
+            })
+        }
+        _ => return Err(ParseError::unexpected(token, &[TokenType::Number])),
+    };
+    keep(&mut number.tokens, token);
+    Ok(number)
 }
 
 fn parse_character(lexer: &mut Peekable<Lexer>) -> Result<Character> {
-    let token = lexer.next().unwrap_or_else(|| panic!(UNEXPECTED_NONE));
+    let token = expect_token(lexer, TokenType::Character)?;
 
-    match token.kind {
-        Some(TokenKind::Character(character)) => Ok(Character::new(character)),
-        _ => Err(format!("Expected character, found {}", token)),
-    }
+    let mut character = match token.kind {
+        Some(TokenKind::Character(character)) => Character::new(character, token.span),
+        _ => return Err(ParseError::unexpected(token, &[TokenType::Character])),
+    };
+    keep(&mut character.tokens, token);
+    Ok(character)
+}
+
+fn parse_string(lexer: &mut Peekable<Lexer>) -> Result<StringLiteral> {
+    let token = expect_token(lexer, TokenType::String)?;
+
+    let mut string = match token.kind {
+        Some(TokenKind::String(ref string)) => StringLiteral::new(string.as_str(), token.span),
+        _ => return Err(ParseError::unexpected(token, &[TokenType::String])),
+    };
+    keep(&mut string.tokens, token);
+    Ok(string)
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use token::Position;
+
+    fn span(start: usize, end: usize) -> Span {
+        Span::new(Position::new(start, 0, start), Position::new(end, 0, end))
+    }
 
     #[test]
     fn test_parse_abstraction() {
-        let result = parse_abstraction(&mut Lexer::new("\\x y. x"));
+        let result = parse_abstraction(&mut Lexer::new("\\x y. x"), Context::Free);
         let expected = Ok(AbstractionExpression::new(
-            vec![Identifier::new("x"), Identifier::new("y")],
-            Expression::from(ApplicationExpression::new(vec![Expression::from(
-                VariableExpression::new(Identifier::new("x")),
-            )])),
+            vec![Identifier::new("x", span(1, 2)), Identifier::new("y", span(3, 4))],
+            Expression::from(ApplicationExpression::new(
+                vec![Expression::from(VariableExpression::new(Identifier::new(
+                    "x",
+                    span(6, 7),
+                )))],
+                span(6, 7),
+            )),
+            span(0, 7),
         ));
         assert_eq!(expected, result);
     }
 
     #[test]
     fn test_parse_application() {
-        let result = parse_application(&mut Lexer::new("x y z"));
-        let expected = Ok(ApplicationExpression::new(vec![
-            Expression::from(VariableExpression::new(Identifier::new("x"))),
-            Expression::from(VariableExpression::new(Identifier::new("y"))),
-            Expression::from(VariableExpression::new(Identifier::new("z"))),
-        ]));
+        let result = parse_application(&mut Lexer::new("x y z"), Context::Free);
+        let expected = Ok(ApplicationExpression::new(
+            vec![
+                Expression::from(VariableExpression::new(Identifier::new("x", span(0, 1)))),
+                Expression::from(VariableExpression::new(Identifier::new("y", span(2, 3)))),
+                Expression::from(VariableExpression::new(Identifier::new("z", span(4, 5)))),
+            ],
+            span(0, 5),
+        ));
+        assert_eq!(expected, result);
+
+        let result = parse_application(&mut Lexer::new("(x) (y)"), Context::Free);
+        let expected = Ok(ApplicationExpression::new(
+            vec![
+                Expression::from(ApplicationExpression::new(
+                    vec![Expression::from(VariableExpression::new(
+                        Identifier::new("x", span(1, 2)),
+                    ))],
+                    span(1, 2),
+                )),
+                Expression::from(ApplicationExpression::new(
+                    vec![Expression::from(VariableExpression::new(
+                        Identifier::new("y", span(5, 6)),
+                    ))],
+                    span(5, 6),
+                )),
+            ],
+            span(0, 7),
+        ));
+        assert_eq!(expected, result);
+
+        let result = parse_application(&mut Lexer::new(r#"f "a\tb""#), Context::Free);
+        let expected = Ok(ApplicationExpression::new(
+            vec![
+                Expression::from(VariableExpression::new(Identifier::new("f", span(0, 1)))),
+                Expression::from(StringLiteral::new("a\tb", span(2, 8))),
+            ],
+            span(0, 8),
+        ));
         assert_eq!(expected, result);
     }
 
@@ -192,9 +694,9 @@ mod test {
     fn test_parse_paratemers() {
         let result = parse_parameters(&mut Lexer::new("x y z"));
         let expected = Ok(vec![
-            Identifier::new("x"),
-            Identifier::new("y"),
-            Identifier::new("z"),
+            Identifier::new("x", span(0, 1)),
+            Identifier::new("y", span(2, 3)),
+            Identifier::new("z", span(4, 5)),
         ]);
         assert_eq!(expected, result);
     }
@@ -202,12 +704,277 @@ mod test {
     #[test]
     fn test_parse_let() {
         let result = parse_let(&mut Lexer::new("let x = y"));
-        let expected = Ok(LetStatement::new(
-            Identifier::new("x"),
-            ApplicationExpression::new(vec![Expression::from(VariableExpression::new(
-                Identifier::new("y"),
-            ))]),
-        ));
+        let expected = Ok(Statement::from(LetStatement::new(
+            Identifier::new("x", span(4, 5)),
+            ApplicationExpression::new(
+                vec![Expression::from(VariableExpression::new(Identifier::new(
+                    "y",
+                    span(8, 9),
+                )))],
+                span(8, 9),
+            ),
+            span(0, 9),
+        )));
+        assert_eq!(expected, result);
+
+        match parse_let(&mut Lexer::new("/// Docs.\nlet x = y")) {
+            Ok(Statement::Let(statement)) => assert_eq!(Some("Docs.".to_owned()), statement.doc),
+            result => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_parse_let_expression() {
+        let variable = |name, start| {
+            ApplicationExpression::new(
+                vec![Expression::from(VariableExpression::new(Identifier::new(
+                    name,
+                    span(start, start + 1),
+                )))],
+                span(start, start + 1),
+            )
+        };
+
+        let result = parse_expression(&mut Lexer::new("let x = y, z = x in z"));
+        let expected = Ok(Expression::from(LetExpression::new(
+            vec![
+                Binding::new(Identifier::new("x", span(4, 5)), variable("y", 8)),
+                Binding::new(Identifier::new("z", span(11, 12)), variable("x", 15)),
+            ],
+            variable("z", 20),
+            span(0, 21),
+        )));
         assert_eq!(expected, result);
+
+        // At the start of a statement, `in` makes a `let` an expression.
+        let Program(statements) = parse(&mut Lexer::new("let x = y in f let z = x in z;")).unwrap();
+        assert_eq!(1, statements.len());
+        match statements[0] {
+            Statement::Expression(..) => (),
+            ref statement => panic!("{:?}", statement),
+        }
+
+        let result = parse(&mut Lexer::new("let x = y, z = x;"));
+        match result {
+            Err(ParseError::UnexpectedToken { ref expected, .. }) => {
+                assert_eq!(&[TokenType::In], expected.as_slice())
+            }
+            _ => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_parse_let_rec() {
+        let Program(statements) = parse(&mut Lexer::new("let rec x = y and y = x; x;")).unwrap();
+        match statements[0] {
+            Statement::LetRec(ref statement) => {
+                let names: Vec<_> = statement
+                    .bindings
+                    .iter()
+                    .map(|binding| binding.variable.name.as_str())
+                    .collect();
+                assert_eq!(vec!["x", "y"], names);
+                assert_eq!(span(0, 23), statement.span);
+            }
+            ref statement => panic!("{:?}", statement),
+        }
+
+        let result = parse_expression(&mut Lexer::new("let rec x = y and y = x in x"));
+        match result {
+            Ok(Expression::Let(ref expression)) => {
+                assert!(expression.recursive);
+                assert_eq!(2, expression.bindings.len());
+            }
+            _ => panic!("{:?}", result),
+        }
+
+        // Only `let rec` groups bindings with `and`, and only `let` expressions with `,`.
+        for source in &["let x = y and y = x; x;", "let rec x = y, y = x in x;"] {
+            assert!(parse(&mut Lexer::new(source)).is_err(), "{}", source);
+        }
+    }
+
+    #[test]
+    fn test_contextual_keywords() {
+        let source = "let and = \\p q. p q p; let rec = and; let in = (f in); and rec in;";
+        let Program(statements) = parse(&mut Lexer::new(source)).unwrap();
+        let names: Vec<_> = statements
+            .iter()
+            .filter_map(|statement| match statement {
+                Statement::Let(statement) => Some(statement.variable.name.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(vec!["and", "rec", "in"], names);
+
+        // A variable named like a keyword can start a binding, where it cannot end one.
+        let source = "let rec f = and and g = in in let x = let y = f in y in x;";
+        match parse_expression(&mut Lexer::new(source)) {
+            Ok(Expression::Let(ref expression)) => {
+                assert_eq!(2, expression.bindings.len());
+                match *expression.expression {
+                    Expression::Let(ref inner) => assert_eq!(1, inner.bindings.len()),
+                    ref expression => panic!("{:?}", expression),
+                }
+            }
+            result => panic!("{:?}", result),
+        }
+
+        let result = parse(&mut Lexer::new("let rec f = f in;"));
+        match result {
+            Err(ParseError::UnexpectedToken { ref expected, .. }) => {
+                assert_eq!(EXPRESSION_START, expected.as_slice())
+            }
+            _ => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_lossless() {
+        let source = "/* a */ let x = (\\y.y) ;\n// b\nx 'c' \"d\" 1;  \n";
+        let program = parse(&mut Lexer::lossless(source)).unwrap();
+        assert_eq!(source, program.source());
+
+        let sources = ["x ); y;", "let x = ) y; x;", "let rec f = \\x. and ; f", "(x y", " // x\n"];
+        for &source in &sources {
+            let (program, errors) = parse_with_recovery(&mut Lexer::lossless(source));
+            assert!(!errors.is_empty());
+            assert_eq!(source, program.source());
+        }
+
+        let program = parse(&mut Lexer::new(source)).unwrap();
+        assert_eq!("", program.source());
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let result = parse(&mut Lexer::new("let x = y)"));
+        let expected = Err(ParseError::UnexpectedToken {
+            found: box Token::new(TokenKind::RightBracket, span(9, 10)),
+            expected: vec![TokenType::Semicolon],
+            span: span(9, 10),
+        });
+        assert_eq!(expected, result);
+
+        let result = parse(&mut Lexer::new("let x ="));
+        match result {
+            Err(ParseError::UnexpectedEndOfInput { ref expected, .. }) => {
+                assert_eq!(EXPRESSION_START, expected.as_slice())
+            }
+            _ => panic!("{:?}", result),
+        }
+
+        // Numbers of any size parse, only Church numerals are limited by `ast::check_numbers`.
+        let result = parse(&mut Lexer::new("99999999999999999999999;"));
+        assert!(result.is_ok(), "{:?}", result);
+
+        let result = parse(&mut Lexer::new("x '';"));
+        match result {
+            Err(ref error @ ParseError::InvalidLiteral { .. }) => {
+                assert_eq!("Empty character literal", error.to_string())
+            }
+            _ => panic!("{:?}", result),
+        }
+
+        let result = parse(&mut Lexer::new("let x = y; /* y;"));
+        match result {
+            Err(ParseError::UnterminatedComment { span: error }) => assert_eq!(span(11, 16), error),
+            _ => panic!("{:?}", result),
+        }
+
+        let result = parse(&mut Lexer::new("x ^;"));
+        match result {
+            Err(ParseError::InvalidCharacter { .. }) => (),
+            _ => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_parse_program_without_expression() {
+        for source in &["", "let x = y;", "// comment\n"] {
+            match parse(&mut Lexer::new(source)) {
+                Err(ParseError::UnexpectedEndOfInput { ref expected, .. }) => {
+                    assert_eq!(EXPRESSION_START, expected.as_slice())
+                }
+                result => panic!("{:?}", result),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_misplaced_expression() {
+        let (_, errors) = parse_with_recovery(&mut Lexer::new("\\x. x; let a = b in c; d;"));
+        let spans: Vec<_> = errors.iter().map(|error| error.span()).collect();
+        assert_eq!(vec![span(0, 5), span(7, 21)], spans);
+        assert_eq!(
+            "Only the last statement can be an expression",
+            format!("{}", errors[0])
+        );
+    }
+
+    #[test]
+    fn test_parse_with_recovery() {
+        let (Program(statements), errors) =
+            parse_with_recovery(&mut Lexer::new("let x = ) y; let y = \\z. z; (x; ^; y;"));
+
+        assert_eq!(3, errors.len());
+        match errors[0] {
+            ParseError::UnexpectedToken {
+                found: box Token {
+                    kind: Some(TokenKind::RightBracket),
+                    ..
+                },
+                ..
+            } => (),
+            ref error => panic!("{:?}", error),
+        }
+        match errors[1] {
+            ParseError::UnexpectedToken {
+                found: box Token {
+                    kind: Some(TokenKind::Semicolon),
+                    ..
+                },
+                ..
+            } => (),
+            ref error => panic!("{:?}", error),
+        }
+        match errors[2] {
+            ParseError::InvalidCharacter { .. } => (),
+            ref error => panic!("{:?}", error),
+        }
+
+        assert_eq!(5, statements.len());
+        assert_eq!(
+            Statement::from(ErrorStatement::new(span(0, 11))),
+            statements[0]
+        );
+        match statements[1] {
+            Statement::Let(..) => (),
+            ref statement => panic!("{:?}", statement),
+        }
+        assert_eq!(
+            Statement::from(ErrorStatement::new(span(28, 31))),
+            statements[2]
+        );
+        assert_eq!(
+            Statement::from(ErrorStatement::new(span(32, 33))),
+            statements[3]
+        );
+        match statements[4] {
+            Statement::Expression(..) => (),
+            ref statement => panic!("{:?}", statement),
+        }
+    }
+
+    #[test]
+    fn test_parse_error_display() {
+        let error = parse(&mut Lexer::new("(x;")).unwrap_err();
+        assert_eq!("Expected ')', found ';'", format!("{}", error));
+
+        let error = parse(&mut Lexer::new("let 1")).unwrap_err();
+        assert_eq!(r#"Expected identifier, found "1""#, format!("{}", error));
+
+        let error = parse(&mut Lexer::new(r"\x")).unwrap_err();
+        assert_eq!("Expected '.', found end of input", format!("{}", error));
     }
 }
@@ -0,0 +1,124 @@
+use natural::Natural;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+/// A built-in function on arbitrary-precision natural numbers, available when
+/// `ast::Options::primitives` is set. All of them take two integers.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Primitive {
+    Add,
+    /// Truncated at zero, like the usual Church numeral subtraction.
+    Sub,
+    Mul,
+    /// Integer division.
+    Div,
+    /// Returns a Church boolean.
+    Eq,
+    /// Returns a Church boolean.
+    Lt,
+}
+
+pub static PRIMITIVES: &[Primitive] = &[
+    Primitive::Add,
+    Primitive::Sub,
+    Primitive::Mul,
+    Primitive::Div,
+    Primitive::Eq,
+    Primitive::Lt,
+];
+
+/// Built-in names for converting between integers and Church numerals, with the terms they
+/// stand for.
+pub static CONVERSIONS: &[(&str, &str)] = &[
+    (
+        "to_church",
+        r"(\f. (\x. f (\v. x x v)) (\x. f (\v. x x v)))
+          (\to_church n f x. eq n 0 x (f (to_church (sub n 1) f x)));",
+    ),
+    ("from_church", r"\n. n (add 1) 0;"),
+];
+
+/// What applying a primitive gives.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Value {
+    Integer(Natural),
+    Boolean(bool),
+}
+
+impl Primitive {
+    pub fn from_name(name: &str) -> Option<Primitive> {
+        PRIMITIVES
+            .iter()
+            .find(|primitive| primitive.name() == name)
+            .cloned()
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Primitive::Add => "add",
+            Primitive::Sub => "sub",
+            Primitive::Mul => "mul",
+            Primitive::Div => "div",
+            Primitive::Eq => "eq",
+            Primitive::Lt => "lt",
+        }
+    }
+
+    pub fn arity(self) -> usize {
+        2
+    }
+
+    /// Applies the primitive, or returns `None` on division by zero, which leaves the
+    /// application stuck.
+    pub fn apply(self, m: &Natural, n: &Natural) -> Option<Value> {
+        match self {
+            Primitive::Add => Some(Value::Integer(m.add(n))),
+            Primitive::Sub => Some(Value::Integer(m.saturating_sub(n))),
+            Primitive::Mul => Some(Value::Integer(m.mul(n))),
+            Primitive::Div => m.div_rem(n).map(|(quotient, _)| Value::Integer(quotient)),
+            Primitive::Eq => Some(Value::Boolean(m == n)),
+            Primitive::Lt => Some(Value::Boolean(m < n)),
+        }
+    }
+}
+
+/// Whether `name` is built in when primitives are enabled.
+pub fn is_builtin(name: &str) -> bool {
+    Primitive::from_name(name).is_some() || CONVERSIONS.iter().any(|&(n, _)| n == name)
+}
+
+impl Display for Primitive {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_apply() {
+        let apply = |primitive: Primitive, m: u64, n: u64| {
+            primitive.apply(&Natural::from(m), &Natural::from(n))
+        };
+        let integer = |n: u64| Some(Value::Integer(Natural::from(n)));
+        assert_eq!(integer(5), apply(Primitive::Add, 2, 3));
+        assert_eq!(integer(0), apply(Primitive::Sub, 2, 3));
+        assert_eq!(integer(3), apply(Primitive::Div, 7, 2));
+        assert_eq!(Some(Value::Boolean(true)), apply(Primitive::Lt, 2, 3));
+        assert_eq!(
+            Some(Value::Integer("18446744073709551616".parse().unwrap())),
+            apply(Primitive::Add, u64::MAX, 1)
+        );
+        assert_eq!(None, apply(Primitive::Div, 1, 0));
+    }
+
+    #[test]
+    fn test_names() {
+        assert_eq!(Some(Primitive::Eq), Primitive::from_name("eq"));
+        assert_eq!(None, Primitive::from_name("to_church"));
+        assert!(is_builtin("to_church"));
+        assert!(!is_builtin("pred"));
+    }
+}
@@ -0,0 +1,356 @@
+use ast::{Direction, Expression};
+use std::collections::HashSet;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::ptr;
+
+/// How bound variables are written.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Notation {
+    /// Source names, renamed where needed to avoid capture: `\f x. f (f x)`.
+    Named,
+    /// De Bruijn indices: `\ \ 1 (1 0)`.
+    Indices,
+}
+
+impl Default for Notation {
+    fn default() -> Notation {
+        Notation::Named
+    }
+}
+
+pub struct Printer<'a> {
+    expression: &'a Expression,
+    notation: Notation,
+    highlight: Option<Highlight<'a>>,
+}
+
+/// A subterm to surround with markers such as terminal color codes.
+struct Highlight<'a> {
+    expression: &'a Expression,
+    before: &'a str,
+    after: &'a str,
+}
+
+impl<'a> Printer<'a> {
+    pub fn new(expression: &'a Expression, notation: Notation) -> Printer<'a> {
+        Printer {
+            expression,
+            notation,
+            highlight: None,
+        }
+    }
+
+    /// Surrounds the subterm at `path` with `before` and `after`.
+    pub fn highlight(
+        mut self,
+        path: &[Direction],
+        before: &'a str,
+        after: &'a str,
+    ) -> Printer<'a> {
+        self.highlight = self
+            .expression
+            .subterm(path)
+            .map(|expression| Highlight {
+                expression,
+                before,
+                after,
+            });
+        self
+    }
+}
+
+impl<'a> Display for Printer<'a> {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self.notation {
+            Notation::Named => {
+                let mut constants = HashSet::new();
+                collect_constants(self.expression, &mut constants);
+                Named {
+                    constants: &constants,
+                    binders: Vec::new(),
+                    highlight: self.highlight.as_ref(),
+                }.expression(f, self.expression)
+            }
+            Notation::Indices => indices(f, self.expression, self.highlight.as_ref()),
+        }
+    }
+}
+
+/// Collects the names that binders must not take, which includes primitives.
+fn collect_constants<'a>(expression: &'a Expression, constants: &mut HashSet<&'a str>) {
+    let mut stack = vec![expression];
+    while let Some(expression) = stack.pop() {
+        match expression {
+            Expression::Variable { .. } | Expression::Integer { .. } => {}
+            Expression::Constant { name } => {
+                constants.insert(name);
+            }
+            Expression::Primitive { primitive } => {
+                constants.insert(primitive.name());
+            }
+            Expression::Abstraction { expression, .. } => stack.push(expression),
+            Expression::Application {
+                applicand,
+                argument,
+            } => {
+                stack.push(argument);
+                stack.push(applicand);
+            }
+        }
+    }
+}
+
+/// What is left to print, kept on an explicit stack since terms can nest deeper than the call
+/// stack allows.
+enum Task<'a> {
+    Expression(&'a Expression),
+    Parenthesized(&'a Expression),
+    Text(&'a str),
+    /// Forgets the binders introduced since there were this many.
+    Truncate(usize),
+}
+
+/// Writes what goes before `expression` and pushes the tasks for what goes after it.
+fn open<'a>(
+    f: &mut Formatter,
+    tasks: &mut Vec<Task<'a>>,
+    expression: &Expression,
+    parenthesized: bool,
+    highlight: Option<&Highlight<'a>>,
+) -> fmt::Result {
+    if let Some(highlight) = highlight.filter(|h| ptr::eq(h.expression, expression)) {
+        f.write_str(highlight.before)?;
+        tasks.push(Task::Text(highlight.after));
+    }
+    if parenthesized {
+        f.write_str("(")?;
+        tasks.push(Task::Text(")"));
+    }
+    Ok(())
+}
+
+/// Pushes the tasks for printing an application, in reverse order.
+fn application<'a>(tasks: &mut Vec<Task<'a>>, applicand: &'a Expression, argument: &'a Expression) {
+    tasks.push(match argument {
+        Expression::Abstraction { .. } | Expression::Application { .. } => {
+            Task::Parenthesized(argument)
+        }
+        _ => Task::Expression(argument),
+    });
+    tasks.push(Task::Text(" "));
+    tasks.push(match applicand {
+        Expression::Abstraction { .. } => Task::Parenthesized(applicand),
+        _ => Task::Expression(applicand),
+    });
+}
+
+struct Named<'a> {
+    constants: &'a HashSet<&'a str>,
+    binders: Vec<String>,
+    highlight: Option<&'a Highlight<'a>>,
+}
+
+impl<'a> Named<'a> {
+    fn expression(&mut self, f: &mut Formatter, expression: &'a Expression) -> fmt::Result {
+        let mut tasks = vec![Task::Expression(expression)];
+
+        while let Some(task) = tasks.pop() {
+            let expression = match task {
+                Task::Expression(expression) => {
+                    open(f, &mut tasks, expression, false, self.highlight)?;
+                    expression
+                }
+                Task::Parenthesized(expression) => {
+                    open(f, &mut tasks, expression, true, self.highlight)?;
+                    expression
+                }
+                Task::Text(text) => {
+                    f.write_str(text)?;
+                    continue;
+                }
+                Task::Truncate(depth) => {
+                    self.binders.truncate(depth);
+                    continue;
+                }
+            };
+
+            match expression {
+                Expression::Variable { index } => match index {
+                    Some(index) if *index < self.binders.len() => {
+                        f.write_str(&self.binders[self.binders.len() - 1 - index])?
+                    }
+                    Some(index) => write!(f, "#{}", index - self.binders.len())?,
+                    None => f.write_str("None")?,
+                },
+
+                Expression::Constant { name } => f.write_str(name)?,
+
+                Expression::Integer { value } => value.fmt(f)?,
+
+                Expression::Primitive { primitive } => primitive.fmt(f)?,
+
+                Expression::Abstraction { .. } => {
+                    tasks.push(Task::Truncate(self.binders.len()));
+
+                    let mut body = expression;
+                    f.write_str(r"\")?;
+                    while let Expression::Abstraction { name, expression } = body {
+                        let name = self.fresh(name.hint.as_ref().map_or("x", |name| name));
+                        write!(f, "{}", name)?;
+                        self.binders.push(name);
+                        body = expression;
+                        if let Expression::Abstraction { .. } = body {
+                            f.write_str(" ")?;
+                        }
+                    }
+                    f.write_str(". ")?;
+
+                    tasks.push(Task::Expression(body));
+                }
+
+                Expression::Application {
+                    applicand,
+                    argument,
+                } => application(&mut tasks, applicand, argument),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Picks a name based on `hint` that neither shadows an enclosing binder nor captures a
+    /// constant, by replacing its numeric suffix.
+    fn fresh(&self, hint: &str) -> String {
+        let is_taken =
+            |name: &str| self.constants.contains(name) || self.binders.iter().any(|b| b == name);
+
+        if !is_taken(hint) {
+            return hint.to_owned();
+        }
+
+        let base = hint.trim_end_matches(|c: char| c.is_ascii_digit());
+        let base = if base.is_empty() { "x" } else { base };
+        (1..)
+            .map(|n| format!("{}{}", base, n))
+            .find(|name| !is_taken(name))
+            .unwrap()
+    }
+}
+
+fn indices<'a>(
+    f: &mut Formatter,
+    expression: &'a Expression,
+    highlight: Option<&Highlight<'a>>,
+) -> fmt::Result {
+    let mut tasks = vec![Task::Expression(expression)];
+
+    while let Some(task) = tasks.pop() {
+        let expression = match task {
+            Task::Expression(expression) => {
+                open(f, &mut tasks, expression, false, highlight)?;
+                expression
+            }
+            Task::Parenthesized(expression) => {
+                open(f, &mut tasks, expression, true, highlight)?;
+                expression
+            }
+            Task::Text(text) => {
+                f.write_str(text)?;
+                continue;
+            }
+            Task::Truncate(..) => continue,
+        };
+
+        match expression {
+            Expression::Variable { index } => match index {
+                Some(index) => index.fmt(f)?,
+                None => f.write_str("None")?,
+            },
+
+            Expression::Constant { name } => f.write_str(name)?,
+
+            Expression::Integer { value } => value.fmt(f)?,
+
+            Expression::Primitive { primitive } => primitive.fmt(f)?,
+
+            Expression::Abstraction { expression, .. } => {
+                f.write_str(r"\ ")?;
+                tasks.push(Task::Expression(expression));
+            }
+
+            Expression::Application {
+                applicand,
+                argument,
+            } => application(&mut tasks, applicand, argument),
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ast::Options;
+    use lexer::Lexer;
+    use parser::parse;
+
+    fn translate(source: &str) -> Expression {
+        let program = parse(&mut Lexer::new(source)).unwrap();
+        Expression::from_cst_program_with(
+            &program,
+            &Options {
+                open_terms: true,
+                ..Options::default()
+            },
+        ).unwrap()
+    }
+
+    #[test]
+    fn test_print_named() {
+        assert_eq!(r"\f x. f (f x)", translate(r"2;").to_string());
+        assert_eq!(
+            r"\x y. y x",
+            translate(r"\x. \y. y x;").display(Notation::Named).to_string()
+        );
+        assert_eq!(r"(\x. x) y", translate(r"(\x. x) y;").to_string());
+        assert_eq!(r"\x. x (\y. y) x", translate(r"\x. x (\y. y) x;").to_string());
+    }
+
+    #[test]
+    fn test_print_named_avoids_capture() {
+        assert_eq!(r"\x x1. x1", translate(r"\x. \x. x;").to_string());
+        assert_eq!(r"x (\x1. x1)", translate(r"x (\x. x);").to_string());
+        assert_eq!(r"\x2 x3. x3 x1", translate(r"\x2 x2. x2 x1;").to_string());
+    }
+
+    #[test]
+    fn test_print_highlight() {
+        let expression = translate(r"(\x. x) ((\y. y) z);");
+        let path = [Direction::Argument];
+        assert_eq!(
+            r"(\x. x) [((\y. y) z)]",
+            expression
+                .display(Notation::Named)
+                .highlight(&path, "[", "]")
+                .to_string()
+        );
+        assert_eq!(
+            r"[(\ 0) ((\ 0) z)]",
+            expression
+                .display(Notation::Indices)
+                .highlight(&[], "[", "]")
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn test_print_indices() {
+        assert_eq!(r"\ \ 1 (1 0)", translate(r"2;").display(Notation::Indices).to_string());
+        assert_eq!(
+            r"(\ 0) (\ 0)",
+            translate(r"(\x. x) (\x. x);").display(Notation::Indices).to_string()
+        );
+    }
+}
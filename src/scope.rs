@@ -0,0 +1,235 @@
+use cst::{
+    AbstractionExpression, ApplicationExpression, Expression, ExpressionStatement, Identifier,
+    LetExpression, LetRecStatement, LetStatement, Program, Statement, VariableExpression,
+};
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use token::Span;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct UnboundVariable {
+    pub name: String,
+    pub span: Span,
+    /// Set if a plain `let` refers to the variable it defines, which needs `let rec`.
+    pub self_reference: bool,
+}
+
+impl UnboundVariable {
+    fn new(identifier: &Identifier) -> UnboundVariable {
+        UnboundVariable {
+            name: identifier.name.clone(),
+            span: identifier.span,
+            self_reference: false,
+        }
+    }
+}
+
+impl Display for UnboundVariable {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, r#"Unbound variable "{}""#, self.name)?;
+        if self.self_reference {
+            write!(f, r#", use "let rec" for a definition that refers to itself"#)?;
+        }
+        Ok(())
+    }
+}
+
+/// Finds every variable that is not bound by an enclosing abstraction or an earlier `let`.
+pub fn check(program: &Program) -> Vec<UnboundVariable> {
+    check_with(program, |_| false)
+}
+
+/// Like `check`, but also accepts the names `is_global` accepts, such as
+/// `primitive::is_builtin`.
+pub fn check_with<F>(program: &Program, is_global: F) -> Vec<UnboundVariable>
+where
+    F: Fn(&str) -> bool,
+{
+    let Program(statements) = program;
+    let mut scopes = Vec::new();
+    let mut errors = Vec::new();
+
+    for statement in statements {
+        match statement {
+            Statement::Expression(ExpressionStatement { expression, .. }) => {
+                check_expression(expression, &mut scopes, &mut errors)
+            }
+
+            Statement::Let(LetStatement {
+                variable,
+                expression,
+                ..
+            }) => {
+                check_definition(variable, expression, &mut scopes, &mut errors);
+                scopes.push(variable.name.as_str());
+            }
+
+            Statement::LetRec(LetRecStatement { bindings, .. }) => {
+                scopes.extend(bindings.iter().map(|binding| binding.variable.name.as_str()));
+                for binding in bindings {
+                    check_expression(&binding.expression, &mut scopes, &mut errors);
+                }
+            }
+
+            Statement::Error(..) => (),
+        }
+    }
+
+    errors.retain(|error| !is_global(&error.name));
+    errors
+}
+
+/// Checks the definition of `variable` by a plain `let`, which does not see itself.
+fn check_definition<'a>(
+    variable: &Identifier,
+    expression: &'a Expression,
+    scopes: &mut Vec<&'a str>,
+    errors: &mut Vec<UnboundVariable>,
+) {
+    let start = errors.len();
+    check_expression(expression, scopes, errors);
+    for error in &mut errors[start..] {
+        error.self_reference = error.name == variable.name;
+    }
+}
+
+fn check_expression<'a>(
+    expression: &'a Expression,
+    scopes: &mut Vec<&'a str>,
+    errors: &mut Vec<UnboundVariable>,
+) {
+    match expression {
+        Expression::Variable(VariableExpression { identifier, .. }) => {
+            if !scopes.contains(&identifier.name.as_str()) {
+                errors.push(UnboundVariable::new(identifier));
+            }
+        }
+
+        Expression::Abstraction(AbstractionExpression {
+            parameters,
+            expression,
+            ..
+        }) => {
+            let depth = scopes.len();
+            scopes.extend(parameters.iter().map(|parameter| parameter.name.as_str()));
+            check_expression(expression, scopes, errors);
+            scopes.truncate(depth);
+        }
+
+        Expression::Application(ApplicationExpression { expressions, .. }) => {
+            for expression in expressions {
+                check_expression(expression, scopes, errors);
+            }
+        }
+
+        Expression::Let(LetExpression {
+            bindings,
+            expression,
+            recursive: true,
+            ..
+        }) => {
+            let depth = scopes.len();
+            scopes.extend(bindings.iter().map(|binding| binding.variable.name.as_str()));
+            for binding in bindings {
+                check_expression(&binding.expression, scopes, errors);
+            }
+            check_expression(expression, scopes, errors);
+            scopes.truncate(depth);
+        }
+
+        Expression::Let(LetExpression {
+            bindings,
+            expression,
+            recursive: false,
+            ..
+        }) => {
+            let depth = scopes.len();
+            for binding in bindings {
+                check_definition(&binding.variable, &binding.expression, scopes, errors);
+                scopes.push(binding.variable.name.as_str());
+            }
+            check_expression(expression, scopes, errors);
+            scopes.truncate(depth);
+        }
+
+        Expression::Number(..) | Expression::Character(..) | Expression::String(..) => (),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use lexer::Lexer;
+    use parser::parse;
+    use token::Position;
+
+    #[test]
+    fn test_check() {
+        let program = parse(&mut Lexer::new(r"let f = \x. x y; let g = \y. f y; f z;")).unwrap();
+        let expected = vec![
+            UnboundVariable {
+                name: "y".to_owned(),
+                span: Span::new(Position::new(14, 0, 14), Position::new(15, 0, 15)),
+                self_reference: false,
+            },
+            UnboundVariable {
+                name: "z".to_owned(),
+                span: Span::new(Position::new(36, 0, 36), Position::new(37, 0, 37)),
+                self_reference: false,
+            },
+        ];
+        assert_eq!(expected, check(&program));
+    }
+
+    #[test]
+    fn test_check_let_does_not_see_itself() {
+        let program = parse(&mut Lexer::new(r"let f = \x. f x; f;")).unwrap();
+        let result = check(&program);
+        assert_eq!(1, result.len());
+        assert_eq!("f", result[0].name);
+        assert!(result[0].self_reference);
+        assert_eq!(
+            r#"Unbound variable "f", use "let rec" for a definition that refers to itself"#,
+            result[0].to_string()
+        );
+
+        let program = parse(&mut Lexer::new(r"\f. let f = \x. f x, g = g in f;")).unwrap();
+        let result = check(&program);
+        assert_eq!(1, result.len());
+        assert_eq!("g", result[0].name);
+        assert!(result[0].self_reference);
+    }
+
+    #[test]
+    fn test_check_let_rec() {
+        let source = r"let rec even = \n. odd n and odd = \n. even n; even;";
+        assert_eq!(Vec::<UnboundVariable>::new(), check(&parse(&mut Lexer::new(source)).unwrap()));
+
+        let source = r"(let rec f = \x. f (g x) in f) f;";
+        let names: Vec<_> = check(&parse(&mut Lexer::new(source)).unwrap())
+            .into_iter()
+            .map(|error| error.name)
+            .collect();
+        assert_eq!(vec!["g", "f"], names);
+    }
+
+    #[test]
+    fn test_check_let_expression() {
+        let source = r"\x. let y = x, z = y z in let w = w in z y;";
+        let program = parse(&mut Lexer::new(source)).unwrap();
+        let names: Vec<_> = check(&program).into_iter().map(|error| error.name).collect();
+        assert_eq!(vec!["z", "w"], names);
+
+        let program = parse(&mut Lexer::new(r"(let y = \x. x in y) y;")).unwrap();
+        let names: Vec<_> = check(&program).into_iter().map(|error| error.name).collect();
+        assert_eq!(vec!["y"], names);
+    }
+
+    #[test]
+    fn test_check_with_globals() {
+        let program = parse(&mut Lexer::new(r"let f = \x. add x y; f 1;")).unwrap();
+        let result = check_with(&program, |name| name == "add");
+        assert_eq!(1, result.len());
+        assert_eq!("y", result[0].name);
+    }
+}
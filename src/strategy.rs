@@ -0,0 +1,76 @@
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// Which redex gets reduced next, and when reduction stops.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Strategy {
+    /// Leftmost outermost redex first, under abstractions and inside arguments, until β-normal
+    /// form.
+    NormalOrder,
+    /// Arguments are reduced before they are substituted; abstractions are values.
+    ApplicativeOrder,
+    /// Leftmost outermost redex first, stopping at weak head normal form.
+    CallByName,
+    /// Call-by-name with every argument evaluated at most once. Single steps are the same as
+    /// call-by-name, only `Expression::reduce` shares work.
+    CallByNeed,
+    /// Leftmost outermost redex first, under abstractions but never inside arguments.
+    HeadNormalForm,
+}
+
+pub static STRATEGIES: &[Strategy] = &[
+    Strategy::NormalOrder,
+    Strategy::ApplicativeOrder,
+    Strategy::CallByName,
+    Strategy::CallByNeed,
+    Strategy::HeadNormalForm,
+];
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct InvalidStrategy(pub String);
+
+impl Default for Strategy {
+    fn default() -> Strategy {
+        Strategy::CallByName
+    }
+}
+
+impl Display for Strategy {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Strategy::NormalOrder => "normal",
+            Strategy::ApplicativeOrder => "applicative",
+            Strategy::CallByName => "name",
+            Strategy::CallByNeed => "need",
+            Strategy::HeadNormalForm => "head",
+        })
+    }
+}
+
+impl FromStr for Strategy {
+    type Err = InvalidStrategy;
+
+    fn from_str(s: &str) -> Result<Strategy, InvalidStrategy> {
+        STRATEGIES
+            .iter()
+            .find(|strategy| strategy.to_string() == s)
+            .cloned()
+            .ok_or_else(|| InvalidStrategy(s.to_owned()))
+    }
+}
+
+impl Display for InvalidStrategy {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Invalid strategy \"{}\", expected one of {}",
+            self.0,
+            STRATEGIES
+                .iter()
+                .map(|strategy| format!("\"{}\"", strategy))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+}
@@ -1,14 +1,20 @@
 use std::fmt;
 use std::fmt::{Display, Formatter};
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct Token {
     pub kind: Option<TokenKind>,
-    pub line: usize,
-    pub column: usize,
+    pub span: Span,
+    /// The `///` doc comment lines right before the token, without their markers.
+    pub doc: Option<String>,
+    /// The source text of the token, only kept by `Lexer::lossless`.
+    pub text: Option<String>,
+    /// The whitespace and comment tokens right before the token, only kept by
+    /// `Lexer::lossless`.
+    pub trivia: Vec<Token>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum TokenKind {
     LeftBracket,
     RightBracket,
@@ -16,32 +22,143 @@ pub enum TokenKind {
     Dot,
     Equal,
     Semicolon,
+    Comma,
     Let,
+    /// `rec`, `and` and `in` are read as identifiers and only made keywords by the parser, where
+    /// it expects them, so that they remain usable as variable names.
+    Rec,
+    And,
+    In,
     Identifier(String),
     Number(String),
     Character(char),
+    String(String),
     InvalidCharacter(char),
+    InvalidLiteral(LiteralError),
+    /// A `/*` without its matching `*/`.
+    UnterminatedComment,
+    /// Trivia, see `Token::trivia`.
+    Whitespace,
+    Comment,
+}
+
+/// Why a character or string literal could not be read.
+#[derive(Debug, PartialEq, Clone)]
+pub enum LiteralError {
+    EmptyCharacter,
+    /// More than one character between single quotes.
+    LongCharacter,
+    UnterminatedCharacter,
+    UnterminatedString,
+    /// The escape sequence as written, such as `\q` or `\u{d800}`.
+    InvalidEscape(String),
+}
+
+/// The kind of a token without its payload, used to describe what a parser expected.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum TokenType {
+    LeftBracket,
+    RightBracket,
+    Lambda,
+    Dot,
+    Equal,
+    Semicolon,
+    Comma,
+    Let,
+    Rec,
+    And,
+    In,
+    Identifier,
+    Number,
+    Character,
+    String,
+}
+
+/// A point in the source. `offset` counts bytes, `line` and `column` count from zero.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct Position {
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// A range of source text. `end` is exclusive.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Position {
+    pub fn new(offset: usize, line: usize, column: usize) -> Position {
+        Position {
+            offset,
+            line,
+            column,
+        }
+    }
+}
+
+impl Span {
+    pub fn new(start: Position, end: Position) -> Span {
+        Span { start, end }
+    }
+
+    /// Returns a span from the start of `self` to the end of `other`.
+    pub fn to(&self, other: Span) -> Span {
+        Span::new(self.start, other.end)
+    }
 }
 
 impl Token {
-    pub fn new<T>(kind: T, line: usize, column: usize) -> Token
+    pub fn new<T>(kind: T, span: Span) -> Token
     where
         T: Into<Option<TokenKind>>,
     {
         Token {
             kind: kind.into(),
-            line,
-            column,
+            span,
+            doc: None,
+            text: None,
+            trivia: Vec::new(),
+        }
+    }
+}
+
+impl TokenKind {
+    pub fn token_type(&self) -> Option<TokenType> {
+        match self {
+            TokenKind::LeftBracket => Some(TokenType::LeftBracket),
+            TokenKind::RightBracket => Some(TokenType::RightBracket),
+            TokenKind::Lambda => Some(TokenType::Lambda),
+            TokenKind::Dot => Some(TokenType::Dot),
+            TokenKind::Equal => Some(TokenType::Equal),
+            TokenKind::Semicolon => Some(TokenType::Semicolon),
+            TokenKind::Comma => Some(TokenType::Comma),
+            TokenKind::Let => Some(TokenType::Let),
+            TokenKind::Rec => Some(TokenType::Rec),
+            TokenKind::And => Some(TokenType::And),
+            TokenKind::In => Some(TokenType::In),
+            TokenKind::Identifier(..) => Some(TokenType::Identifier),
+            TokenKind::Number(..) => Some(TokenType::Number),
+            TokenKind::Character(..) => Some(TokenType::Character),
+            TokenKind::String(..) => Some(TokenType::String),
+            TokenKind::InvalidCharacter(..)
+            | TokenKind::InvalidLiteral(..)
+            | TokenKind::UnterminatedComment
+            | TokenKind::Whitespace
+            | TokenKind::Comment => None,
         }
     }
 }
 
 impl Display for Token {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let Position { line, column, .. } = self.span.start;
         if let Some(ref kind) = self.kind {
-            write!(f, "{} ({}:{})", kind, self.line, self.column)
+            write!(f, "{} ({}:{})", kind, line, column)
         } else {
-            write!(f, "None ({}:{})", self.line, self.column)
+            write!(f, "None ({}:{})", line, column)
         }
     }
 }
@@ -55,15 +172,66 @@ impl Display for TokenKind {
             TokenKind::Dot => write!(f, "'.'"),
             TokenKind::Equal => write!(f, "'='"),
             TokenKind::Semicolon => write!(f, "';'"),
+            TokenKind::Comma => write!(f, "','"),
             TokenKind::Let => write!(f, "'let'"),
+            TokenKind::Rec => write!(f, "'rec'"),
+            TokenKind::And => write!(f, "'and'"),
+            TokenKind::In => write!(f, "'in'"),
             TokenKind::Identifier(identifier) => write!(f, r#""{}""#, identifier),
             TokenKind::Number(number) => write!(f, r#""{}""#, number),
-            TokenKind::Character(character) => write!(f, "'{}'", character),
+            TokenKind::Character(character) => write!(f, "{:?}", character),
+            TokenKind::String(string) => write!(f, "{:?}", string),
             TokenKind::InvalidCharacter(c) => write!(f, "'{}'", c),
+            TokenKind::InvalidLiteral(error) => error.fmt(f),
+            TokenKind::UnterminatedComment => write!(f, "'/*'"),
+            TokenKind::Whitespace => write!(f, "whitespace"),
+            TokenKind::Comment => write!(f, "comment"),
+        }
+    }
+}
+
+impl Display for LiteralError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            LiteralError::EmptyCharacter => write!(f, "Empty character literal"),
+            LiteralError::LongCharacter => {
+                write!(f, "Character literal with more than one character")
+            }
+            LiteralError::UnterminatedCharacter => write!(f, "Unterminated character literal"),
+            LiteralError::UnterminatedString => write!(f, "Unterminated string literal"),
+            LiteralError::InvalidEscape(escape) => write!(f, "Invalid escape sequence {}", escape),
         }
     }
 }
 
+impl Display for TokenType {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            TokenType::LeftBracket => write!(f, "'('"),
+            TokenType::RightBracket => write!(f, "')'"),
+            TokenType::Lambda => write!(f, r"'\'"),
+            TokenType::Dot => write!(f, "'.'"),
+            TokenType::Equal => write!(f, "'='"),
+            TokenType::Semicolon => write!(f, "';'"),
+            TokenType::Comma => write!(f, "','"),
+            TokenType::Let => write!(f, "'let'"),
+            TokenType::Rec => write!(f, "'rec'"),
+            TokenType::And => write!(f, "'and'"),
+            TokenType::In => write!(f, "'in'"),
+            TokenType::Identifier => write!(f, "identifier"),
+            TokenType::Number => write!(f, "number"),
+            TokenType::Character => write!(f, "character"),
+            TokenType::String => write!(f, "string"),
+        }
+    }
+}
+
+impl Display for Position {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.line + 1, self.column + 1)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -71,18 +239,22 @@ mod test {
     #[test]
     fn test_token_display() {
         for (token, result) in &[
-            (Token::new(TokenKind::LeftBracket, 0, 0), "'('"),
-            (Token::new(TokenKind::RightBracket, 0, 0), "')'"),
-            (Token::new(TokenKind::Lambda, 0, 0), r"'\'"),
-            (Token::new(TokenKind::Dot, 0, 0), "'.'"),
-            (Token::new(TokenKind::Equal, 0, 0), "'='"),
-            (Token::new(TokenKind::Semicolon, 0, 0), "';'"),
-            (Token::new(TokenKind::Let, 0, 0), "'let'"),
+            (Token::new(TokenKind::LeftBracket, Span::default()), "'('"),
+            (Token::new(TokenKind::RightBracket, Span::default()), "')'"),
+            (Token::new(TokenKind::Lambda, Span::default()), r"'\'"),
+            (Token::new(TokenKind::Dot, Span::default()), "'.'"),
+            (Token::new(TokenKind::Equal, Span::default()), "'='"),
+            (Token::new(TokenKind::Semicolon, Span::default()), "';'"),
+            (Token::new(TokenKind::Comma, Span::default()), "','"),
+            (Token::new(TokenKind::Let, Span::default()), "'let'"),
+            (Token::new(TokenKind::Rec, Span::default()), "'rec'"),
+            (Token::new(TokenKind::And, Span::default()), "'and'"),
+            (Token::new(TokenKind::In, Span::default()), "'in'"),
             (
-                Token::new(TokenKind::Identifier("x".to_owned()), 0, 0),
+                Token::new(TokenKind::Identifier("x".to_owned()), Span::default()),
                 r#""x""#,
             ),
-            (Token::new(TokenKind::InvalidCharacter('?'), 0, 0), "'?'"),
+            (Token::new(TokenKind::InvalidCharacter('?'), Span::default()), "'?'"),
         ] {
             assert_eq!(format!("{}", token), format!("{} (0:0)", result));
         }
@@ -0,0 +1,54 @@
+extern crate clumsy;
+
+use clumsy::ast::{Expression, Limits};
+use clumsy::decode;
+use clumsy::lexer::Lexer;
+use clumsy::parser::parse;
+use clumsy::printer::Notation;
+use clumsy::strategy::Strategy;
+
+// Numerals are chains of applications as deep as their value, far beyond what recursive
+// traversals survive on a test thread's stack.
+static N: usize = 100_000;
+
+fn translate(source: &str) -> Expression {
+    Expression::from_cst_program(&parse(&mut Lexer::new(source)).unwrap()).unwrap()
+}
+
+#[test]
+fn test_deep_term_basics() {
+    let numeral = translate(&format!("{};", N));
+    assert_eq!(N + 3 + N, numeral.size());
+    assert_eq!(numeral, numeral.clone());
+    assert_ne!(numeral, translate(&format!("{};", N - 1)));
+
+    let printed = numeral.to_string();
+    assert!(printed.starts_with(r"\f x. f (f (f "));
+    assert!(printed.contains("(f x))"));
+    assert_eq!(N - 1, printed.matches('(').count());
+
+    let printed = numeral.display(Notation::Indices).to_string();
+    assert!(printed.starts_with(r"\ \ 1 (1 (1 "));
+    assert!(printed.contains("(1 0))"));
+
+    assert_eq!(Some(N), decode::number(&numeral, &Limits::default()));
+}
+
+#[test]
+fn test_reduce_deep_term() {
+    let source = format!(r"(\n f x. f (n f x)) {};", N);
+    let expected = translate(&format!("{};", N + 1));
+
+    for &strategy in &[
+        Strategy::NormalOrder,
+        Strategy::ApplicativeOrder,
+        Strategy::CallByName,
+        Strategy::CallByNeed,
+        Strategy::HeadNormalForm,
+    ] {
+        let result = translate(&source).reduce(strategy);
+        assert_eq!(Some(N + 1), decode::number(&result, &Limits::default()), "{}", strategy);
+    }
+
+    assert_eq!(expected, translate(&source).normalize());
+}
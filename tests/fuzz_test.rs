@@ -0,0 +1,67 @@
+extern crate clumsy;
+
+use clumsy::ast::Expression;
+use clumsy::formatter;
+use clumsy::lexer::Lexer;
+use clumsy::parser;
+
+static SOURCES: &[&str] = &[
+    include_str!("factorial.clumsy"),
+    include_str!("fibonacci.clumsy"),
+];
+
+static SUFFIXES: &[&str] = &["", ";", ")", "(", ".", "=", "'", "'x", "/", "\n", ",", " in x;"];
+
+/// Every prefix of the sample programs, cut at each character, followed by each suffix.
+fn truncated_sources() -> impl Iterator<Item = String> {
+    SOURCES.iter().flat_map(|source| {
+        source.char_indices().flat_map(move |(end, _)| {
+            SUFFIXES
+                .iter()
+                .map(move |suffix| format!("{}{}", &source[..end], suffix))
+        })
+    })
+}
+
+fn parse_and_translate(source: &str) {
+    if let Ok(program) = parser::parse(&mut Lexer::new(source)) {
+        let _ = Expression::from_cst_program(&program);
+    }
+}
+
+#[test]
+fn test_parse_every_prefix_without_panic() {
+    for source in truncated_sources() {
+        parse_and_translate(&source);
+    }
+}
+
+#[test]
+fn test_parse_truncated_program_is_error() {
+    for source in SOURCES {
+        let source = source.trim_end();
+        let end = source.rfind(';').unwrap();
+        for (end, _) in source[..end].char_indices() {
+            assert!(parser::parse(&mut Lexer::new(&source[..end])).is_err());
+        }
+    }
+}
+
+#[test]
+fn test_lossless_round_trip() {
+    for source in truncated_sources() {
+        let (program, _) = parser::parse_with_recovery(&mut Lexer::lossless(&source));
+        assert_eq!(source, program.source());
+    }
+}
+
+#[test]
+fn test_format_is_idempotent() {
+    for source in truncated_sources() {
+        if let Ok(program) = parser::parse(&mut Lexer::lossless(&source)) {
+            let formatted = formatter::format(&program);
+            let program = parser::parse(&mut Lexer::lossless(&formatted)).unwrap();
+            assert_eq!(formatted, formatter::format(&program));
+        }
+    }
+}
@@ -1,23 +1,286 @@
 extern crate clumsy;
 
+use clumsy::ast;
+use clumsy::ast::{Direction, Expression, Limits, Options, TranslationError};
+use clumsy::decode;
+use clumsy::formatter;
+use clumsy::lexer::Lexer;
+use clumsy::parser::parse;
+use clumsy::strategy::{Strategy, STRATEGIES};
+
+fn translate(source: &str) -> Expression {
+    Expression::from_cst_program(&parse(&mut Lexer::new(source)).unwrap()).unwrap()
+}
+
+fn evaluate(source: &str) -> Expression {
+    translate(source).evaluate()
+}
+
 #[test]
 fn test_factorial_of_3_is_equal_to_6() {
     let source = include_str!("factorial.clumsy");
-    println!(
-        "{}",
-        clumsy::ast::Expression::from_cst_program(
-            &clumsy::parser::parse(&mut clumsy::lexer::Lexer::new(source)).unwrap()
-        ).evaluate()
-    );
+    assert_eq!(Some(true), decode::boolean(&evaluate(source), &Limits::default()));
 }
 
 #[test]
 fn test_6th_fibonacci_number_is_equal_to_8() {
     let source = include_str!("fibonacci.clumsy");
-    println!(
-        "{}",
-        clumsy::ast::Expression::from_cst_program(
-            &clumsy::parser::parse(&mut clumsy::lexer::Lexer::new(source)).unwrap()
-        ).evaluate()
+    assert_eq!(Some(true), decode::boolean(&evaluate(source), &Limits::default()));
+}
+
+#[test]
+fn test_factorial_of_3() {
+    let source =
+        include_str!("factorial.clumsy").replace("is_equal (factorial 3) 6;", "factorial 3;");
+    for &strategy in STRATEGIES {
+        let result = translate(&source).reduce(strategy);
+        assert_eq!(Some(6), decode::number(&result, &Limits::default()), "{}", strategy);
+    }
+}
+
+#[test]
+fn test_normal_forms_of_equal_numbers_are_identical() {
+    let source =
+        include_str!("factorial.clumsy").replace("is_equal (factorial 3) 6;", "factorial 3;");
+    let factorial = translate(&source).normalize();
+    let six = translate("6;").normalize();
+    assert_eq!(six, factorial);
+    assert_eq!(six.to_string(), factorial.to_string());
+}
+
+#[test]
+fn test_strategies() {
+    let reduce = |source, strategy| translate(source).reduce(strategy).to_string();
+
+    let source = r"(\x y. y x) ((\z. z) (\z. z));";
+    assert_eq!(r"\y. y ((\z. z) (\z. z))", reduce(source, Strategy::CallByName));
+    assert_eq!(r"\y. y (\z. z)", reduce(source, Strategy::ApplicativeOrder));
+    assert_eq!(r"\y. y ((\z. z) (\z. z))", reduce(source, Strategy::HeadNormalForm));
+    assert_eq!(r"\y. y (\z. z)", reduce(source, Strategy::NormalOrder));
+
+    let source = r"(\x. x) (\y. (\z. z) y);";
+    assert_eq!(r"\y. (\z. z) y", reduce(source, Strategy::CallByName));
+    assert_eq!(r"\y. (\z. z) y", reduce(source, Strategy::ApplicativeOrder));
+    assert_eq!(r"\y. y", reduce(source, Strategy::HeadNormalForm));
+    assert_eq!(r"\y. y", reduce(source, Strategy::NormalOrder));
+}
+
+#[test]
+fn test_unicode_source() {
+    let source = "let id' = λα. α; let twice = λf x. f (f x); twice id' (λβ. β);";
+    assert_eq!(r"\β. β", translate(source).normalize().to_string());
+}
+
+#[test]
+fn test_call_by_need() {
+    let limits = Limits::default();
+    let source = include_str!("factorial.clumsy");
+    let result = translate(source).reduce(Strategy::CallByNeed);
+    assert_eq!(Some(true), decode::boolean(&result, &limits));
+
+    let source = include_str!("fibonacci.clumsy");
+    let result = translate(source).reduce(Strategy::CallByNeed);
+    assert_eq!(Some(true), decode::boolean(&result, &limits));
+
+    // Far out of reach without sharing.
+    let source = include_str!("fibonacci.clumsy").replace("(fibonacci 6) 8", "(fibonacci 15) 610");
+    let result = translate(&source).reduce(Strategy::CallByNeed);
+    assert_eq!(Some(true), decode::boolean(&result, &limits));
+}
+
+#[test]
+fn test_steps() {
+    let steps = translate(r"(\x y. x) ((\z. z) (\z. z));")
+        .steps(Strategy::NormalOrder)
+        .map(|step| (step.expression.to_string(), step.redex))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec![
+            (r"(\x y. x) ((\z. z) (\z. z))".to_owned(), Some(vec![])),
+            (r"\y. (\z. z) (\z. z)".to_owned(), Some(vec![Direction::Body])),
+            (r"\y z. z".to_owned(), None),
+        ],
+        steps
+    );
+}
+
+#[test]
+fn test_stats() {
+    let source = r"let id = \x. x; let two = \f x. f (f x); two id id;";
+    for &strategy in STRATEGIES {
+        let (outcome, stats) = translate(source).reduce_with_stats(strategy, &Limits::default());
+        assert_eq!(r"\x. x", outcome.expression().to_string(), "{}", strategy);
+        assert_eq!(6, stats.beta_reductions, "{}", strategy);
+        assert_eq!(Some(&2), stats.definitions.get("id"), "{}", strategy);
+        assert_eq!(Some(&2), stats.definitions.get("two"), "{}", strategy);
+        assert!(stats.substitutions > 0, "{}", strategy);
+        assert!(stats.peak_size > 0, "{}", strategy);
+    }
+}
+
+#[test]
+fn test_call_by_need_shares_work() {
+    for source in &[include_str!("factorial.clumsy"), include_str!("fibonacci.clumsy")] {
+        let stats = |strategy| {
+            let (outcome, stats) =
+                translate(source).reduce_with_stats(strategy, &Limits::default());
+            assert_eq!(Some(true), decode::boolean(outcome.expression(), &Limits::default()));
+            stats
+        };
+        let (name, need) = (stats(Strategy::CallByName), stats(Strategy::CallByNeed));
+        assert!(need.beta_reductions < name.beta_reductions);
+        assert!(need.substitutions < name.substitutions);
+    }
+}
+
+#[test]
+fn test_primitives() {
+    let options = Options {
+        primitives: true,
+        ..Options::default()
+    };
+    let reduce = |source, strategy| {
+        Expression::from_cst_program_with(&parse(&mut Lexer::new(source)).unwrap(), &options)
+            .unwrap()
+            .reduce(strategy)
+    };
+
+    for &strategy in STRATEGIES {
+        let result = reduce(r"let double = \n. add n n; sub (double (mul 3 4)) 4;", strategy);
+        assert_eq!("20", result.to_string(), "{}", strategy);
+
+        let result = reduce(r"lt 1 2 (div 7 2) 0;", strategy);
+        assert_eq!("3", result.to_string(), "{}", strategy);
+
+        let result = reduce(r"from_church (\f x. f (f (f x)));", strategy);
+        assert_eq!("3", result.to_string(), "{}", strategy);
+
+        let result = reduce(r"div 1 (sub 1 1);", strategy);
+        assert_eq!("div 1 0", result.to_string(), "{}", strategy);
+
+        let result = reduce(r"add 18446744073709551615 1;", strategy);
+        assert_eq!("18446744073709551616", result.to_string(), "{}", strategy);
+    }
+
+    let result = reduce(r"to_church (add 1 2);", Strategy::NormalOrder);
+    assert_eq!(r"\f x. f (f (f x))", result.to_string());
+    let limits = Limits::default();
+    let result = reduce(r"to_church 3;", Strategy::CallByNeed);
+    assert_eq!(Some(3), decode::number(&result, &limits));
+    let result = reduce(r"eq (mul 2 3) 6;", Strategy::CallByNeed);
+    assert_eq!(Some(true), decode::boolean(&result, &limits));
+}
+
+#[test]
+fn test_big_integers() {
+    let options = Options {
+        primitives: true,
+        ..Options::default()
+    };
+    let source = r"
+        let Y = \f. (\x. f (x x)) (\x. f (x x));
+        let factorial = Y (\factorial n. eq n 0 1 (mul n (factorial (sub n 1))));
+        factorial 50;
+    ";
+    let program = parse(&mut Lexer::new(source)).unwrap();
+    assert!(ast::check_numbers(&program, &options).is_empty());
+
+    let result = Expression::from_cst_program_with(&program, &options)
+        .unwrap()
+        .reduce(Strategy::CallByNeed);
+    assert_eq!(
+        "30414093201713378043612608166064768844377641568960512000000000000",
+        decode::natural(&result, &Limits::default()).unwrap().to_string()
     );
+
+    let program = parse(&mut Lexer::new("100000000000000000000;")).unwrap();
+    assert_eq!(1, ast::check_numbers(&program, &Options::default()).len());
+    assert!(ast::check_numbers(&program, &options).is_empty());
+
+    // Church numerals stop well before `u64`, which could never be unfolded.
+    let program = parse(&mut Lexer::new("99999999999;")).unwrap();
+    match Expression::from_cst_program(&program).unwrap_err() {
+        TranslationError::NumberTooLarge(error) => assert_eq!("99999999999", error.value),
+        error => panic!("{:?}", error),
+    }
+    assert!(Expression::from_cst_program_with(&program, &options).is_ok());
+}
+
+#[test]
+fn test_strings() {
+    let source = r#"
+        let append = \xs ys c n. xs c (ys c n);
+        append "Hello, " "world!\n";
+    "#;
+    for &strategy in STRATEGIES {
+        assert_eq!(
+            Some("Hello, world!\n".to_owned()),
+            decode::string(&translate(source).reduce(strategy), &Limits::default())
+        );
+    }
+
+    let options = Options {
+        lists: ast::ListEncoding::Scott,
+        ..Options::default()
+    };
+    let source = r#"let tail = \xs. xs xs (\h t. t); tail "abc";"#;
+    let program = parse(&mut Lexer::new(source)).unwrap();
+    let result = Expression::from_cst_program_with(&program, &options)
+        .unwrap()
+        .reduce(Strategy::CallByNeed);
+    assert_eq!(Some("bc".to_owned()), decode::string(&result, &Limits::default()));
+}
+
+#[test]
+fn test_lossless_round_trip() {
+    for source in &[include_str!("factorial.clumsy"), include_str!("fibonacci.clumsy")] {
+        let program = parse(&mut Lexer::lossless(source)).unwrap();
+        assert_eq!(*source, program.source());
+    }
+}
+
+#[test]
+fn test_format() {
+    for source in &[include_str!("factorial.clumsy"), include_str!("fibonacci.clumsy")] {
+        let program = parse(&mut Lexer::lossless(source)).unwrap();
+        assert_eq!(*source, formatter::format(&program));
+    }
+}
+
+#[test]
+fn test_let_expressions() {
+    let source = r"
+        let twice = \f x. f (f x);
+        let double = \n. n;
+        let quadruple = \x.
+            let double = \n f y. n f (n f y),
+                twice_double = twice double
+            in twice_double x;
+        quadruple 3;
+    ";
+    for &strategy in STRATEGIES {
+        let result = translate(source).reduce(strategy);
+        assert_eq!(Some(12), decode::number(&result, &Limits::default()), "{}", strategy);
+    }
+}
+
+#[test]
+fn test_let_rec() {
+    let source = r"
+        let true = \x y. x;
+        let false = \x y. y;
+        let pred = \n f x. n (\g h. h (g f)) (\u. x) (\u. u);
+        let is_zero = \n. n (\x. false) true;
+        let rec is_even = \n. is_zero n (\u. true) (\u. is_odd (pred n)) n
+        and is_odd = \n. is_zero n (\u. false) (\u. is_even (pred n)) n;
+        let sum = \n.
+            let rec go = \n acc. is_zero n (\u. acc) (\u. go (pred n) (\f x. n f (acc f x))) n
+            in go n 0;
+        is_even 4 (is_odd 3 (sum 4) 0) 1;
+    ";
+    // The branches are abstractions so that reducing arguments first does not recurse forever.
+    for &strategy in STRATEGIES {
+        let result = translate(source).reduce(strategy);
+        assert_eq!(Some(10), decode::number(&result, &Limits::default()), "{}", strategy);
+    }
 }
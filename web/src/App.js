@@ -32,15 +32,13 @@ let cond = \p then else. p then else;
 let is_zero = \n. n (\x. false) true;
 let is_equal = \m n. and (is_zero (sub m n)) (is_zero (sub n m));
 
-// Fixed point combinator
-let Y = \f. (\x. f (x x)) (\x. f (x x));
-
-// Factorial function
-let factorial_impl = \f n.
+// Factorial function, whose branches wait for an argument so that only the chosen one is
+// evaluated, whatever the strategy
+let rec factorial = \n.
     cond (is_zero n)
-        1
-        (mul n (f (pred n)));
-let factorial = Y factorial_impl;
+        (\u. 1)
+        (\u. mul n (factorial (pred n)))
+        n;
 
 // Go!
 is_equal (factorial 3) 6;`
@@ -54,11 +52,19 @@ class App extends React.Component {
     this.state = {
       source: localStorage.getItem('clumsy.source') || initial_source,
       keyboardHandler: localStorage.getItem('clumsy.keyboardHandler'),
+      strategy: localStorage.getItem('clumsy.strategy') || 'name',
+      primitives: localStorage.getItem('clumsy.primitives') === 'true',
       ready: false,
       result: null,
+      html: null,
+      playing: false,
     }
   }
 
+  componentWillUnmount() {
+    this.closeSession()
+  }
+
   componentDidMount() {
     import('./clumsy_web.js').then(wasm => {
       this.wasm = wasm
@@ -67,18 +73,111 @@ class App extends React.Component {
   }
 
   handleConfigChange(state) {
-    this.setState({ keyboardHandler: state.keyboardHandler })
-    localStorage.setItem('clumsy.keyboardHandler', state.keyboardHandler)
+    this.closeSession()
+    this.setState(state)
+    for (const key of Object.keys(state)) {
+      localStorage.setItem(`clumsy.${key}`, state[key])
+    }
   }
 
   handleEditorUpdate(source) {
+    this.closeSession()
     this.setState({ source })
     localStorage.setItem('clumsy.source', source)
   }
 
   handleEvaluation() {
     if (this.state.ready) {
-      this.setState({ result: this.wasm.evaluate(this.state.source) })
+      this.closeSession()
+      this.setState({
+        result: this.wasm.evaluate(
+          this.state.source,
+          this.state.strategy,
+          this.state.primitives
+        ),
+        html: null,
+      })
+    }
+  }
+
+  handleFormat() {
+    if (this.state.ready) {
+      try {
+        this.handleEditorUpdate(this.wasm.format(this.state.source))
+      } catch (error) {
+        this.closeSession()
+        this.setState({ result: String(error), html: null })
+      }
+    }
+  }
+
+  // Parses the source once and keeps stepping the same term until the source or the strategy
+  // changes.
+  openSession() {
+    if (!this.session) {
+      try {
+        this.session = new this.wasm.Session(
+          this.state.source,
+          this.state.strategy,
+          this.state.primitives
+        )
+      } catch (error) {
+        this.setState({ result: String(error), html: null })
+        return null
+      }
+    }
+    return this.session
+  }
+
+  closeSession() {
+    this.handlePause()
+    if (this.session) {
+      this.session.free()
+      this.session = null
+    }
+  }
+
+  showSession() {
+    this.setState({
+      result: `${this.session.count()}: `,
+      html: this.session.render_html(),
+    })
+  }
+
+  handleStep() {
+    if (this.state.ready && this.openSession()) {
+      this.session.step()
+      this.showSession()
+    }
+  }
+
+  handlePlay() {
+    if (!this.state.ready || this.state.playing || !this.openSession()) {
+      return
+    }
+    this.showSession()
+    this.timer = setInterval(() => {
+      if (!this.session.step()) {
+        this.handlePause()
+      }
+      this.showSession()
+    }, 200)
+    this.setState({ playing: true })
+  }
+
+  handlePause() {
+    if (this.timer) {
+      clearInterval(this.timer)
+      this.timer = null
+      this.setState({ playing: false })
+    }
+  }
+
+  handleReset() {
+    if (this.session) {
+      this.handlePause()
+      this.session.reset()
+      this.showSession()
     }
   }
 
@@ -93,8 +192,16 @@ class App extends React.Component {
         <MenuBar
           className={classes.row}
           keyboardHandler={this.state.keyboardHandler}
+          strategy={this.state.strategy}
+          primitives={this.state.primitives}
           onChange={state => this.handleConfigChange(state)}
+          playing={this.state.playing}
           onEvaluation={() => this.handleEvaluation()}
+          onFormat={() => this.handleFormat()}
+          onStep={() => this.handleStep()}
+          onPlay={() => this.handlePlay()}
+          onPause={() => this.handlePause()}
+          onReset={() => this.handleReset()}
         />
 
         <Grid container className={classes.row} spacing={16}>
@@ -108,7 +215,7 @@ class App extends React.Component {
           </Grid>
 
           <Grid item xs={12} sm={6}>
-            <Result value={this.state.result} />
+            <Result value={this.state.result} html={this.state.html} />
           </Grid>
         </Grid>
       </React.Fragment>
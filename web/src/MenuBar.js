@@ -1,12 +1,27 @@
 import React from 'react'
 import Button from '@material-ui/core/Button'
 import FormControl from '@material-ui/core/FormControl'
+import FormControlLabel from '@material-ui/core/FormControlLabel'
 import Grid from '@material-ui/core/Grid'
 import InputLabel from '@material-ui/core/InputLabel'
 import MenuItem from '@material-ui/core/MenuItem'
 import Select from '@material-ui/core/Select'
+import Switch from '@material-ui/core/Switch'
 
-const MenuBar = ({ className, keyboardHandler, onChange, onEvaluation }) => (
+const MenuBar = ({
+  className,
+  keyboardHandler,
+  strategy,
+  primitives,
+  onChange,
+  playing,
+  onEvaluation,
+  onFormat,
+  onStep,
+  onPlay,
+  onPause,
+  onReset,
+}) => (
   <Grid
     container
     justify="space-between"
@@ -33,10 +48,46 @@ const MenuBar = ({ className, keyboardHandler, onChange, onEvaluation }) => (
       </FormControl>
     </Grid>
 
+    <Grid item>
+      <FormControl>
+        <InputLabel>Strategy</InputLabel>
+        <Select
+          value={strategy}
+          onChange={ev => onChange({ strategy: ev.target.value })}
+        >
+          <MenuItem value="normal">Normal&nbsp;Order</MenuItem>
+          <MenuItem value="applicative">Applicative&nbsp;Order</MenuItem>
+          <MenuItem value="name">Call&nbsp;by&nbsp;Name</MenuItem>
+          <MenuItem value="need">Call&nbsp;by&nbsp;Need</MenuItem>
+          <MenuItem value="head">Head&nbsp;Normal&nbsp;Form</MenuItem>
+        </Select>
+      </FormControl>
+    </Grid>
+
+    <Grid item>
+      <FormControlLabel
+        label="Integers"
+        control={
+          <Switch
+            checked={primitives}
+            onChange={ev => onChange({ primitives: ev.target.checked })}
+          />
+        }
+      />
+    </Grid>
+
     <Grid item>
       <Button variant="raised" color="primary" onClick={() => onEvaluation()}>
         Evaluate
       </Button>
+      <Button onClick={() => onStep()}>Step</Button>
+      {playing ? (
+        <Button onClick={() => onPause()}>Pause</Button>
+      ) : (
+        <Button onClick={() => onPlay()}>Play</Button>
+      )}
+      <Button onClick={() => onReset()}>Reset</Button>
+      <Button onClick={() => onFormat()}>Format</Button>
     </Grid>
   </Grid>
 )
@@ -5,11 +5,17 @@ import { withStyles } from '@material-ui/core/styles'
 const styles = theme => ({
   result: {
     fontFamily: 'monospace',
+    whiteSpace: 'pre-wrap',
   },
 })
 
-const Result = ({ value, classes }) => (
-  <Paper className={classes.result}>{value}</Paper>
+// `html` is the current term of a stepping session, already escaped, with the next redex
+// marked up.
+const Result = ({ value, html, classes }) => (
+  <Paper className={classes.result}>
+    {value}
+    {html && <span dangerouslySetInnerHTML={{ __html: html }} />}
+  </Paper>
 )
 
 export default withStyles(styles)(Result)
@@ -3,14 +3,194 @@
 extern crate clumsy;
 extern crate wasm_bindgen;
 
-use clumsy::ast::Expression;
+use clumsy::ast;
+use clumsy::ast::{Expression, Limits, Options, Outcome, Step, Steps};
+use clumsy::diagnostic::Diagnostic;
+use clumsy::formatter;
 use clumsy::lexer::Lexer;
 use clumsy::parser;
+use clumsy::primitive;
+use clumsy::printer::Notation;
+use clumsy::scope;
+use clumsy::strategy::Strategy;
+use clumsy::token::Span;
+use std::fmt::Display;
 use wasm_bindgen::prelude::*;
 
+static STEP_LIMIT: usize = 10_000_000;
+static SIZE_LIMIT: usize = 1_000_000;
+/// In milliseconds.
+static TIME_LIMIT: f64 = 10_000.0;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = Date)]
+    fn now() -> f64;
+}
+
+#[wasm_bindgen]
+pub fn evaluate(source: &str, strategy: &str, primitives: bool) -> String {
+    let (expression, strategy) = match translate(source, strategy, primitives) {
+        Ok(translated) => translated,
+        Err(err) => return err,
+    };
+
+    let deadline = now() + TIME_LIMIT;
+    let cancelled = || now() > deadline;
+    let limits = Limits {
+        steps: Some(STEP_LIMIT),
+        size: Some(SIZE_LIMIT),
+        cancelled: Some(&cancelled),
+    };
+
+    match expression.reduce_with_limits(strategy, &limits) {
+        Outcome::Normal(expression) => format!("{}", expression),
+        Outcome::StepLimit(expression) => {
+            format!("Step limit reached, stopped at:\n{}", expression)
+        }
+        Outcome::SizeLimit(expression) => {
+            format!("Size limit reached, stopped at:\n{}", expression)
+        }
+        Outcome::Cancelled(expression) => format!("Timed out, stopped at:\n{}", expression),
+    }
+}
+
+/// Lays out `source` in the canonical layout, failing with the rendered syntax errors.
+#[wasm_bindgen]
+pub fn format(source: &str) -> Result<String, JsValue> {
+    let (cst, errors) = parser::parse_with_recovery(&mut Lexer::lossless(source));
+    if !errors.is_empty() {
+        let errors = errors.into_iter().map(|err| (err.span(), err));
+        return Err(JsValue::from(report(source, errors)));
+    }
+    Ok(formatter::format(&cst))
+}
+
+/// Steps through the reduction of a program one redex at a time.
 #[wasm_bindgen]
-pub fn evaluate(source: &str) -> String {
-    parser::parse(&mut Lexer::new(source))
-        .map(|ref cst| format!("{}", Expression::from_cst_program(cst).evaluate()))
-        .unwrap_or_else(|err| format!("{}", err))
+pub struct Session {
+    initial: Expression,
+    strategy: Strategy,
+    steps: Steps,
+    current: Step,
+    count: u32,
+}
+
+#[wasm_bindgen]
+impl Session {
+    /// Fails with the rendered diagnostics if `source` does not translate.
+    #[wasm_bindgen(constructor)]
+    pub fn new(source: &str, strategy: &str, primitives: bool) -> Result<Session, JsValue> {
+        let (initial, strategy) =
+            translate(source, strategy, primitives).map_err(JsValue::from)?;
+        let mut steps = initial.clone().steps(strategy);
+        let current = steps.next().unwrap();
+        Ok(Session {
+            initial,
+            strategy,
+            steps,
+            current,
+            count: 0,
+        })
+    }
+
+    /// Contracts the next redex, returning `false` if there is none.
+    pub fn step(&mut self) -> bool {
+        if self.is_normal() {
+            return false;
+        }
+        self.current = self.steps.next().unwrap();
+        self.count += 1;
+        true
+    }
+
+    /// Takes up to `n` steps and returns how many were taken.
+    pub fn run(&mut self, n: u32) -> u32 {
+        let mut taken = 0;
+        while taken < n && self.step() {
+            taken += 1;
+        }
+        taken
+    }
+
+    pub fn reset(&mut self) {
+        self.steps = self.initial.clone().steps(self.strategy);
+        self.current = self.steps.next().unwrap();
+        self.count = 0;
+    }
+
+    pub fn is_normal(&self) -> bool {
+        self.current.redex.is_none()
+    }
+
+    /// The number of steps taken since the start.
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    pub fn render(&self) -> String {
+        self.current.expression.to_string()
+    }
+
+    /// Renders the current term as HTML, with the next redex in a `<mark>` element.
+    pub fn render_html(&self) -> String {
+        let printer = self.current.expression.display(Notation::Named);
+        let html = match self.current.redex {
+            Some(ref redex) => printer.highlight(redex, "\u{0}", "\u{1}").to_string(),
+            None => printer.to_string(),
+        };
+        html.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('\u{0}', "<mark>")
+            .replace('\u{1}', "</mark>")
+    }
+}
+
+/// Parses and checks `source`, rendering any errors.
+fn translate(
+    source: &str,
+    strategy: &str,
+    primitives: bool,
+) -> Result<(Expression, Strategy), String> {
+    let strategy = strategy.parse::<Strategy>().map_err(|err| err.to_string())?;
+
+    let (cst, errors) = parser::parse_with_recovery(&mut Lexer::new(source));
+    if !errors.is_empty() {
+        return Err(report(source, errors.into_iter().map(|err| (err.span(), err))));
+    }
+
+    let options = Options {
+        primitives,
+        ..Options::default()
+    };
+
+    let errors = ast::check_numbers(&cst, &options);
+    if !errors.is_empty() {
+        return Err(report(source, errors.into_iter().map(|err| (err.span, err))));
+    }
+
+    let errors = if primitives {
+        scope::check_with(&cst, primitive::is_builtin)
+    } else {
+        scope::check(&cst)
+    };
+    if !errors.is_empty() {
+        return Err(report(source, errors.into_iter().map(|err| (err.span, err))));
+    }
+
+    let expression = Expression::from_cst_program_with(&cst, &options)
+        .map_err(|err| report(source, Some((err.span(), err)).into_iter()))?;
+    Ok((expression, strategy))
+}
+
+fn report<I, T>(source: &str, errors: I) -> String
+where
+    I: Iterator<Item = (Span, T)>,
+    T: Display,
+{
+    errors
+        .map(|(span, err)| format!("{}", Diagnostic::new(source, span, err)))
+        .collect::<Vec<_>>()
+        .join("\n\n")
 }